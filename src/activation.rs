@@ -0,0 +1,323 @@
+//! Offline license activation, for consumers without network access
+//!
+//! 1. The consumer creates an [`ActivationRequest`] and keeps it around while activation is pending
+//! 2. The request is carried as text to the signing authority, which [grants](ActivationRequest::grant) it
+//! 3. The resulting [`ActivationResponse`] is carried back as text to the consumer
+//! 4. The consumer [verifies](ActivationResponse::verify) the response against its pending request
+//!
+//! ```
+//! use sigserlic::activation::{ActivationRequest, ActivationResponse};
+//! use sigserlic::machine::{Component, Fingerprint};
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//!
+//! // Consumer
+//! let fingerprint = Fingerprint::new(b"my product").with(Component::Hostname, "toto");
+//! let pending = ActivationRequest::new(fingerprint);
+//! let request_text = pending.to_text().unwrap();
+//!
+//! // Signing authority
+//! let request = ActivationRequest::from_text(&request_text).unwrap();
+//! let builder = request.grant::<String, ()>("premium".into());
+//! let response = ActivationResponse::from(signing_key.sign(builder).unwrap());
+//! let response_text = response.to_text().unwrap();
+//!
+//! // Consumer, shipped with the public key of the authority
+//! # let public_key = sigserlic::PublicKey::from(signing_key);
+//! let response = ActivationResponse::<String, ()>::from_text(&response_text).unwrap();
+//! let message = response.verify(&public_key, &pending).unwrap();
+//! assert_eq!(message.data().data(), "premium");
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::SignatureBuilder;
+use crate::error::SignatureError;
+use crate::machine::{Fingerprint, MachineBound};
+use crate::{Message, Nonce, PublicKey, Signature};
+use base64ct::Encoding;
+use jiff::Timestamp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Request to activate a license on a machine, created by the consumer
+pub struct ActivationRequest {
+    fingerprint: Fingerprint,
+    nonce: Nonce,
+    #[serde(with = "crate::timestamp::required")]
+    created_at: Timestamp,
+}
+
+impl ActivationRequest {
+    /// New activation request for the machine identified by `fingerprint`, with a random nonce
+    #[cfg(feature = "generate")]
+    pub fn new(fingerprint: Fingerprint) -> Self {
+        Self::with_nonce(fingerprint, Nonce::generate())
+    }
+
+    /// New activation request for the machine identified by `fingerprint`, with a provided nonce
+    pub fn with_nonce(fingerprint: Fingerprint, nonce: Nonce) -> Self {
+        Self {
+            fingerprint,
+            nonce,
+            created_at: Timestamp::now(),
+        }
+    }
+
+    /// Fingerprint of the machine to activate
+    pub fn fingerprint(&self) -> &Fingerprint {
+        &self.fingerprint
+    }
+
+    /// Nonce identifying this request
+    pub fn nonce(&self) -> Nonce {
+        self.nonce
+    }
+
+    /// Timestamp when the request was created
+    pub fn created_at(&self) -> Timestamp {
+        self.created_at
+    }
+
+    /// Encode request as a short text blob, safe to copy around
+    pub fn to_text(&self) -> Result<String, ActivationError> {
+        let bytes = bincode::serde::encode_to_vec(self, crate::BINCODE_CONFIG)
+            .map_err(|_| ActivationError::Bincode)?;
+        Ok(base64ct::Base64UrlUnpadded::encode_string(&bytes))
+    }
+
+    /// Decode request from a text blob produced by [`ActivationRequest::to_text()`]
+    pub fn from_text(text: &str) -> Result<Self, ActivationError> {
+        let bytes = base64ct::Base64UrlUnpadded::decode_vec(text.trim()).context(TextSnafu)?;
+        let (request, length) = bincode::serde::decode_from_slice(&bytes, crate::BINCODE_CONFIG)
+            .map_err(|_| ActivationError::Bincode)?;
+        if length != bytes.len() {
+            return Err(ActivationError::Bincode);
+        }
+        Ok(request)
+    }
+
+    #[cfg(feature = "sign")]
+    /// Accept the request, binding `data` to it.
+    ///
+    /// The builder needs to be signed by the authority, to produce an [`ActivationResponse`].
+    pub fn grant<'de, T: Serialize + Deserialize<'de>, C>(
+        self,
+        data: T,
+    ) -> SignatureBuilder<Activation<T>, C> {
+        SignatureBuilder::new(Activation {
+            fingerprint: self.fingerprint,
+            nonce: self.nonce,
+            data,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// Data granted by the authority, bound to an [`ActivationRequest`]
+pub struct Activation<T> {
+    fingerprint: Fingerprint,
+    nonce: Nonce,
+    data: T,
+}
+
+impl<T> Activation<T> {
+    /// Data granted by the authority
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Nonce of the granted request
+    pub fn nonce(&self) -> Nonce {
+        self.nonce
+    }
+}
+
+impl<T> MachineBound for Activation<T> {
+    fn fingerprint(&self) -> &Fingerprint {
+        &self.fingerprint
+    }
+}
+
+#[derive(Debug)]
+/// Response of the authority to an [`ActivationRequest`]
+pub struct ActivationResponse<T, C>(Signature<Activation<T>, C>);
+
+impl<T, C> From<Signature<Activation<T>, C>> for ActivationResponse<T, C> {
+    fn from(signature: Signature<Activation<T>, C>) -> Self {
+        Self(signature)
+    }
+}
+
+impl<T: Serialize, C: Serialize> ActivationResponse<T, C> {
+    /// Encode response as a text blob, safe to copy around
+    pub fn to_text(&self) -> Result<String, ActivationError> {
+        let bytes = self.0.to_compact_bytes().context(SignatureSnafu)?;
+        Ok(base64ct::Base64UrlUnpadded::encode_string(&bytes))
+    }
+}
+
+impl<T: DeserializeOwned, C: DeserializeOwned> ActivationResponse<T, C> {
+    /// Decode response from a text blob produced by [`ActivationResponse::to_text()`]
+    pub fn from_text(text: &str) -> Result<Self, ActivationError> {
+        let bytes = base64ct::Base64UrlUnpadded::decode_vec(text.trim()).context(TextSnafu)?;
+        Signature::from_compact_bytes(&bytes)
+            .map(Self)
+            .context(SignatureSnafu)
+    }
+}
+
+impl<'de, T: Serialize + Deserialize<'de>, C> ActivationResponse<T, C> {
+    /// Verify authenticity of the response with [`PublicKey`], and make sure it answers the
+    /// `pending` request of the consumer
+    pub fn verify<CPubKey>(
+        self,
+        public_key: &PublicKey<CPubKey>,
+        pending: &ActivationRequest,
+    ) -> Result<Message<Activation<T>>, ActivationError> {
+        let message = self.0.verify(public_key).context(SignatureSnafu)?;
+
+        let activation = message.data();
+        if activation.nonce != pending.nonce {
+            return Err(ActivationError::NonceMismatch);
+        }
+        if activation.fingerprint != pending.fingerprint {
+            return Err(ActivationError::FingerprintMismatch);
+        }
+
+        Ok(message)
+    }
+
+    /// Get the untrusted comment attached to the response, if set
+    pub fn comment(&self) -> Option<&C> {
+        self.0.comment()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors during offline activation
+pub enum ActivationError {
+    #[snafu(display("decoding text blob"))]
+    /// Text blob is not valid base64
+    Text {
+        /// Original error
+        source: base64ct::Error,
+    },
+    #[snafu(display("decoding request in binary format"))]
+    /// Failed to encode or decode request with [`Bincode`](bincode)
+    Bincode,
+    #[snafu(display("handling signature of response"))]
+    /// The signature of the response is not valid
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+    #[snafu(display("response was issued for another request"))]
+    /// Nonce of response does not match the pending request
+    NonceMismatch,
+    #[snafu(display("response was issued for another machine"))]
+    /// Fingerprint of response does not match the pending request
+    FingerprintMismatch,
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+    use crate::machine::Component;
+    use crate::test_keys::{public_key, signing_key};
+
+    const NONCE_1: [u8; 16] = [1; 16];
+    const NONCE_2: [u8; 16] = [2; 16];
+
+    fn fingerprint(hostname: &str) -> Fingerprint {
+        Fingerprint::new(b"toto mange du gateau")
+            .with(Component::MachineId, "3d1219c7c4c5404aaa1f6d2a48adfda4")
+            .with(Component::Hostname, hostname)
+    }
+
+    fn respond(request: ActivationRequest) -> String {
+        let signing_key: SigningKey<()> = signing_key();
+        let builder = request.grant::<u32, String>(42).comment("toto".into());
+        ActivationResponse::from(signing_key.sign(builder).unwrap())
+            .to_text()
+            .unwrap()
+    }
+
+    #[test]
+    fn request_text_roundtrip() {
+        let request = ActivationRequest::with_nonce(fingerprint("toto"), NONCE_1.into());
+        let text = request.to_text().unwrap();
+        assert!(
+            text.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c))
+        );
+
+        let decoded = ActivationRequest::from_text(&text).unwrap();
+        assert_eq!(decoded.nonce(), request.nonce());
+        assert_eq!(decoded.fingerprint(), request.fingerprint());
+        assert_eq!(decoded.created_at(), request.created_at());
+    }
+
+    #[test]
+    fn activate() {
+        let pending = ActivationRequest::with_nonce(fingerprint("toto"), NONCE_1.into());
+        let request = ActivationRequest::from_text(&pending.to_text().unwrap()).unwrap();
+
+        let response = ActivationResponse::<u32, String>::from_text(&respond(request)).unwrap();
+        assert_eq!(response.comment(), Some(&"toto".into()));
+
+        let message = response.verify(&public_key(), &pending).unwrap();
+        assert_eq!(message.data().data(), &42);
+        assert_eq!(message.data().nonce(), pending.nonce());
+    }
+
+    #[test]
+    fn another_request() {
+        let pending = ActivationRequest::with_nonce(fingerprint("toto"), NONCE_1.into());
+        let other = ActivationRequest::with_nonce(fingerprint("toto"), NONCE_2.into());
+
+        let response = ActivationResponse::<u32, String>::from_text(&respond(other)).unwrap();
+        assert_eq!(
+            response.verify(&public_key(), &pending).unwrap_err(),
+            ActivationError::NonceMismatch
+        );
+    }
+
+    #[test]
+    fn another_machine() {
+        let pending = ActivationRequest::with_nonce(fingerprint("toto"), NONCE_1.into());
+        let other = ActivationRequest::with_nonce(fingerprint("gateau"), NONCE_1.into());
+
+        let response = ActivationResponse::<u32, String>::from_text(&respond(other)).unwrap();
+        assert_eq!(
+            response.verify(&public_key(), &pending).unwrap_err(),
+            ActivationError::FingerprintMismatch
+        );
+    }
+
+    #[test]
+    fn invalid_text() {
+        assert!(matches!(
+            ActivationRequest::from_text("toto mange du gateau").unwrap_err(),
+            ActivationError::Text { .. }
+        ));
+        assert_eq!(
+            ActivationRequest::from_text("dG90bw").unwrap_err(),
+            ActivationError::Bincode
+        );
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let request = ActivationRequest::with_nonce(fingerprint("toto"), NONCE_1.into());
+        let mut bytes = bincode::serde::encode_to_vec(&request, crate::BINCODE_CONFIG).unwrap();
+        bytes.push(0);
+
+        let text = base64ct::Base64UrlUnpadded::encode_string(&bytes);
+        assert_eq!(
+            ActivationRequest::from_text(&text).unwrap_err(),
+            ActivationError::Bincode
+        );
+    }
+}
@@ -0,0 +1,453 @@
+//! ASCII armor for keys and signatures, to travel safely in emails and chats
+//!
+//! The format is close to OpenPGP armor: a `BEGIN` line, optional headers, a blank line, the
+//! content encoded in base64, a CRC-24 checksum and an `END` line. The content is the JSON
+//! serialization of the type, so nothing is lost compared to the usual serde representation.
+//!
+//! ```
+//! use sigserlic::armor::Armor;
+//! # let signing_key = sigserlic::SigningKey::<String>::generate().with_comment("toto".into());
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//!
+//! let armored = public_key.to_armor_with_headers(&[("Comment", "toto")]).unwrap();
+//! assert!(armored.starts_with("-----BEGIN SIGSERLIC PUBLIC KEY-----\nComment: toto\n"));
+//!
+//! let imported = sigserlic::PublicKey::<String>::from_armor(&armored).unwrap();
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::{PublicKey, Signature};
+use base64ct::Encoding;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use snafu::{ResultExt, Snafu};
+
+/// Number of base64 characters per line of content
+const LINE_LEN: usize = 64;
+
+/// `(name, value)` headers of armor
+type Headers = Vec<(String, String)>;
+
+/// Types which can be encoded in ASCII armor
+pub trait Armor: Serialize + DeserializeOwned {
+    /// What is armored, appears in the `BEGIN` and `END` lines
+    const LABEL: &'static str;
+
+    /// Encode in ASCII armor, without headers
+    fn to_armor(&self) -> Result<String, ArmorError> {
+        self.to_armor_with_headers(&[])
+    }
+
+    /// Encode in ASCII armor, with informative `(name, value)` headers which are not protected by
+    /// the checksum, names are made of ASCII letters, digits and `-`
+    fn to_armor_with_headers(&self, headers: &[(&str, &str)]) -> Result<String, ArmorError> {
+        let content = serde_json::to_vec(self).context(JsonSnafu)?;
+        encode(Self::LABEL, headers, &content)
+    }
+
+    /// Decode from ASCII armor, ignoring headers
+    fn from_armor(armored: &str) -> Result<Self, ArmorError> {
+        Self::from_armor_with_headers(armored).map(|(value, _)| value)
+    }
+
+    /// Decode from ASCII armor, with its `(name, value)` headers
+    fn from_armor_with_headers(armored: &str) -> Result<(Self, Headers), ArmorError> {
+        let (headers, content) = decode(Self::LABEL, armored)?;
+        let value = serde_json::from_slice(&content).context(JsonSnafu)?;
+        Ok((value, headers))
+    }
+}
+
+#[cfg(feature = "sign")]
+impl<C: Serialize + DeserializeOwned> Armor for SigningKey<C> {
+    const LABEL: &'static str = "SIGSERLIC SIGNING KEY";
+}
+
+impl<C: Serialize + DeserializeOwned> Armor for PublicKey<C> {
+    const LABEL: &'static str = "SIGSERLIC PUBLIC KEY";
+}
+
+impl<T: Serialize + DeserializeOwned, C: Serialize + DeserializeOwned> Armor for Signature<T, C> {
+    const LABEL: &'static str = "SIGSERLIC SIGNATURE";
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when encoding or decoding ASCII armor
+pub enum ArmorError {
+    #[snafu(display("missing `-----BEGIN {label}-----` line"))]
+    /// Beginning of armor not found
+    Begin {
+        /// Expected label
+        label: &'static str,
+    },
+    #[snafu(display("missing `-----END {label}-----` line"))]
+    /// End of armor not found
+    End {
+        /// Expected label
+        label: &'static str,
+    },
+    #[snafu(display("invalid header `{header}`"))]
+    /// Header is not in the `Name: value` format
+    Header {
+        /// Faulty header
+        header: String,
+    },
+    #[snafu(display("decoding base64"))]
+    /// Content of the armor is not valid base64
+    Base64 {
+        /// Original error
+        source: base64ct::Error,
+    },
+    #[snafu(display("checksum does not match content"))]
+    /// Content of the armor has been altered
+    Checksum,
+    #[snafu(display("content of armor"))]
+    /// Failed to encode or decode content with json
+    Json {
+        /// Original error
+        source: serde_json::Error,
+    },
+}
+
+/// CRC-24 as defined in [RFC 4880](https://www.rfc-editor.org/rfc/rfc4880#section-6.1)
+pub(crate) fn crc24(bytes: &[u8]) -> [u8; 3] {
+    const INIT: u32 = 0xb704ce;
+    const POLY: u32 = 0x1864cfb;
+
+    let mut crc = INIT;
+    for byte in bytes {
+        crc ^= u32::from(*byte) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= POLY;
+            }
+        }
+    }
+    let [_, a, b, c] = crc.to_be_bytes();
+    [a, b, c]
+}
+
+/// Header names are tokens like `Comment` or `Message-ID`, made of ASCII letters, digits and `-`
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Split a `Name: value` line, `None` if it is not a header
+fn split_header(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.split_once(':')?;
+    let separated = value.is_empty() || value.starts_with(' ');
+    (separated && is_header_name(name)).then_some((name, value.trim()))
+}
+
+/// Wrap `content` in armor lines
+pub(crate) fn encode(
+    label: &'static str,
+    headers: &[(&str, &str)],
+    content: &[u8],
+) -> Result<String, ArmorError> {
+    let mut armored = format!("-----BEGIN {label}-----\n");
+
+    for (name, value) in headers {
+        if !is_header_name(name) || value.contains(['\n', '\r']) {
+            return Err(ArmorError::Header {
+                header: format!("{name}: {value}"),
+            });
+        }
+        armored.push_str(&format!("{name}: {value}\n"));
+    }
+    armored.push('\n');
+
+    let encoded = base64ct::Base64::encode_string(content);
+    for line in encoded.as_bytes().chunks(LINE_LEN) {
+        armored.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        armored.push('\n');
+    }
+    armored.push('=');
+    armored.push_str(&base64ct::Base64::encode_string(&crc24(content)));
+    armored.push('\n');
+
+    armored.push_str(&format!("-----END {label}-----\n"));
+    Ok(armored)
+}
+
+/// Wrap `content` in `BEGIN` and `END` lines, without headers nor checksum, like PEM
+pub(crate) fn encode_plain(label: &str, content: &[u8], line_len: usize) -> String {
+    let mut armored = format!("-----BEGIN {label}-----\n");
+    let encoded = base64ct::Base64::encode_string(content);
+    for line in encoded.as_bytes().chunks(line_len) {
+        armored.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        armored.push('\n');
+    }
+    armored.push_str(&format!("-----END {label}-----\n"));
+    armored
+}
+
+/// Extract headers and content of armor, tolerating text around it and lines rewrapped or
+/// indented by email clients
+pub(crate) fn decode(label: &'static str, armored: &str) -> Result<(Headers, Vec<u8>), ArmorError> {
+    let begin = format!("-----BEGIN {label}-----");
+    let end = format!("-----END {label}-----");
+
+    // Leading whitespace is kept to tell wrapped headers apart, the whole armor may be indented
+    let mut lines = armored
+        .lines()
+        .map(str::trim_end)
+        .skip_while(|line| line.trim_start() != begin);
+    let indentation = lines
+        .next()
+        .map(|line| line.len() - line.trim_start().len())
+        .ok_or(ArmorError::Begin { label })?;
+
+    let mut inside = Vec::new();
+    loop {
+        match lines.next() {
+            Some(line) if line.trim_start() == end => break,
+            Some(line) => inside.push(line),
+            None => return Err(ArmorError::End { label }),
+        }
+    }
+
+    // Headers are separated from the body by a blank line, some formats do not have any
+    let body_lines = match inside.iter().position(|line| line.is_empty()) {
+        Some(position) => inside.split_off(position + 1),
+        None => std::mem::take(&mut inside),
+    };
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in inside.into_iter().filter(|line| !line.is_empty()) {
+        // RFC 4880 section 6.2: lines starting with whitespace continue the previous header
+        let continuation = line.len() - line.trim_start().len() > indentation;
+        let line = line.trim_start();
+        match split_header(line) {
+            Some((name, value)) if !continuation => headers.push((name.into(), value.into())),
+            // Header longer than the line, wrapped by an email client, maybe without indentation
+            _ => match headers.last_mut() {
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(line);
+                }
+                None => {
+                    return Err(ArmorError::Header {
+                        header: line.into(),
+                    });
+                }
+            },
+        }
+    }
+
+    let body: String = body_lines
+        .iter()
+        .flat_map(|line| line.chars())
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    // Base64 only has `=` as padding at the very end: a `=` followed by 4 characters is the checksum
+    let (body, checksum) = match body.rfind('=') {
+        Some(position) if position + 5 == body.len() => {
+            let (body, checksum) = body.split_at(position);
+            (body, Some(&checksum[1..]))
+        }
+        _ => (body.as_str(), None),
+    };
+
+    let content = base64ct::Base64::decode_vec(body).context(Base64Snafu)?;
+    if let Some(checksum) = checksum {
+        let checksum = base64ct::Base64::decode_vec(checksum).context(Base64Snafu)?;
+        if checksum != crc24(&content) {
+            return Err(ArmorError::Checksum);
+        }
+    }
+
+    Ok((headers, content))
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::{
+        COMMENTED_SIGNING_KEY_JSON, PUBLIC_KEY_JSON, SIGNATURE_JSON, other_signing_key_with,
+        public_key,
+    };
+
+    const ARMORED_PUBLIC_KEY: &str = "-----BEGIN SIGSERLIC PUBLIC KEY-----
+Comment: testing key, do not use
+
+eyJwdWJsaWNfa2V5IjoiUldTWHJqZnFoMzVZakhhS1FkaWxCcUN5MEMyYlF6TWts
+WGhhTVBHYWY5Ri9lT3VVSTg2THlMQW0iLCJjcmVhdGVkX2F0IjoiMjAyNC0xMi0y
+M1QwMDoxMjo1NC41Mzc1M1oiLCJleHBpcmVkX2F0IjpudWxsfQ==
+=fIgX
+-----END SIGSERLIC PUBLIC KEY-----
+";
+
+    #[test]
+    fn crc24_of_empty_input() {
+        assert_eq!(crc24(b""), [0xb7, 0x04, 0xce]);
+    }
+
+    #[test]
+    fn encode_public_key() {
+        let public_key: PublicKey<()> = public_key();
+        let armored = public_key
+            .to_armor_with_headers(&[("Comment", "testing key, do not use")])
+            .unwrap();
+        assert_eq!(armored, ARMORED_PUBLIC_KEY);
+    }
+
+    #[test]
+    fn decode_public_key() {
+        let (public_key, headers) =
+            PublicKey::<()>::from_armor_with_headers(ARMORED_PUBLIC_KEY).unwrap();
+        assert_eq!(serde_json::to_string(&public_key).unwrap(), PUBLIC_KEY_JSON);
+        assert_eq!(
+            headers,
+            [("Comment".into(), "testing key, do not use".into())]
+        );
+    }
+
+    #[test]
+    fn roundtrip_signing_key() {
+        let signing_key = other_signing_key_with(None, Some(String::from("testing key")));
+        let armored = signing_key.to_armor().unwrap();
+        assert!(armored.starts_with("-----BEGIN SIGSERLIC SIGNING KEY-----\n\n"));
+
+        let imported = SigningKey::<String>::from_armor(&armored).unwrap();
+        assert_eq!(
+            serde_json::to_string(&imported).unwrap(),
+            COMMENTED_SIGNING_KEY_JSON
+        );
+    }
+
+    #[test]
+    fn roundtrip_signature() {
+        let signature: Signature<String, String> = serde_json::from_str(SIGNATURE_JSON).unwrap();
+        let armored = signature.to_armor().unwrap();
+
+        let imported = Signature::<String, String>::from_armor(&armored).unwrap();
+        assert_eq!(serde_json::to_string(&imported).unwrap(), SIGNATURE_JSON);
+    }
+
+    #[test]
+    fn rewrapped_in_email() {
+        let email = format!(
+            "Hello,\r\n\r\nhere is my key:\r\n\r\n{}\r\nCheers\r\n",
+            ARMORED_PUBLIC_KEY
+                .replace("Comment: testing key,", "Comment: testing key,\n")
+                .replace("M1QwMDoxMjo1NC41", "\n  M1QwMDoxMjo1NC41")
+                .replace("==\n=fIgX", "===fIgX")
+                .replace('\n', "  \r\n")
+        );
+
+        let (public_key, headers) = PublicKey::<()>::from_armor_with_headers(&email).unwrap();
+        assert_eq!(serde_json::to_string(&public_key).unwrap(), PUBLIC_KEY_JSON);
+        assert_eq!(
+            headers,
+            [("Comment".into(), "testing key, do not use".into())]
+        );
+    }
+
+    #[test]
+    fn wrapped_url_in_header() {
+        let armored = ARMORED_PUBLIC_KEY.replace(
+            "Comment: testing key, do not use\n",
+            "Comment: testing key, see\n https://example.com/toto\n",
+        );
+        let (_, headers) = PublicKey::<()>::from_armor_with_headers(&armored).unwrap();
+        assert_eq!(
+            headers,
+            [(
+                "Comment".into(),
+                "testing key, see https://example.com/toto".into()
+            )]
+        );
+
+        // Whole armor indented, in a quoted email
+        let quoted: String = armored
+            .lines()
+            .map(|line| format!("    {line}\n"))
+            .collect();
+        let (_, headers) = PublicKey::<()>::from_armor_with_headers(&quoted).unwrap();
+        assert_eq!(
+            headers,
+            [(
+                "Comment".into(),
+                "testing key, see https://example.com/toto".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn unindented_wrapped_header() {
+        let armored = ARMORED_PUBLIC_KEY.replace(
+            "Comment: testing key, do not use\n",
+            "Comment: testing key, see\nhttps://example.com/toto\nVersion: 1\n",
+        );
+        let (_, headers) = PublicKey::<()>::from_armor_with_headers(&armored).unwrap();
+        assert_eq!(
+            headers,
+            [
+                (
+                    "Comment".into(),
+                    "testing key, see https://example.com/toto".into()
+                ),
+                ("Version".into(), "1".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn without_blank_line() {
+        let armored = ARMORED_PUBLIC_KEY.replace("Comment: testing key, do not use\n\n", "");
+        let (_, headers) = PublicKey::<()>::from_armor_with_headers(&armored).unwrap();
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn without_checksum() {
+        let armored = ARMORED_PUBLIC_KEY.replace("=fIgX\n", "");
+        assert!(PublicKey::<()>::from_armor(&armored).is_ok());
+    }
+
+    #[test]
+    fn altered() {
+        let armored = ARMORED_PUBLIC_KEY.replace("eyJwdWJs", "eyJwdWJz");
+        assert!(matches!(
+            PublicKey::<()>::from_armor(&armored).unwrap_err(),
+            ArmorError::Checksum
+        ));
+    }
+
+    #[test]
+    fn wrong_label() {
+        assert!(matches!(
+            Signature::<String, String>::from_armor(ARMORED_PUBLIC_KEY).unwrap_err(),
+            ArmorError::Begin {
+                label: "SIGSERLIC SIGNATURE"
+            }
+        ));
+
+        let truncated = ARMORED_PUBLIC_KEY.replace("-----END SIGSERLIC PUBLIC KEY-----\n", "");
+        assert!(matches!(
+            PublicKey::<()>::from_armor(&truncated).unwrap_err(),
+            ArmorError::End { .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_header() {
+        let public_key: PublicKey<()> = public_key();
+        assert!(matches!(
+            public_key
+                .to_armor_with_headers(&[("Comment", "toto\ngateau")])
+                .unwrap_err(),
+            ArmorError::Header { .. }
+        ));
+        assert!(matches!(
+            public_key
+                .to_armor_with_headers(&[("Toto mange", "du gateau")])
+                .unwrap_err(),
+            ArmorError::Header { .. }
+        ));
+    }
+}
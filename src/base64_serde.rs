@@ -0,0 +1,130 @@
+//! Serialize byte arrays as base64 strings
+
+use alloc::format;
+use alloc::string::String;
+use base64ct::Encoding;
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&base64ct::Base64::encode_string(bytes))
+}
+
+pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // Make sure value is an owned string, some serde implementations will fail on a slice (ex: `ciborium`)
+    let string = String::deserialize(deserializer)?;
+    let bytes = base64ct::Base64::decode_vec(&string).map_err(serde::de::Error::custom)?;
+    let length = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| serde::de::Error::invalid_length(length, &format!("{N} bytes").as_str()))
+}
+
+/// Serialize byte vectors of any size as base64 strings
+pub mod vec {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use base64ct::Encoding;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&base64ct::Base64::encode_string(bytes))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        base64ct::Base64::decode_vec(&string).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serialize byte vectors as base64 strings in human-readable formats, and as raw bytes in binary
+/// formats
+///
+/// Base64 strings, written by binary formats up to 0.2.0, are accepted as well by formats
+/// describing their values.
+pub mod native {
+    use alloc::vec::Vec;
+    use base64ct::Encoding;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            super::vec::serialize(bytes, serializer)
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            super::vec::deserialize(deserializer)
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+
+    /// [`deserialize()`] for a field of a struct, which binary formats may hold as a base64 string
+    /// written up to 0.2.0 when `NAMED` (see [`fields_serde`](crate::fields_serde))
+    pub fn deserialize_field<'de, D, const NAMED: bool>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if NAMED && !deserializer.is_human_readable() {
+            return deserializer.deserialize_any(BytesVisitor);
+        }
+        deserialize(deserializer)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("bytes, or a base64 string")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, string: &str) -> Result<Self::Value, E> {
+            base64ct::Base64::decode_vec(string).map_err(E::custom)
+        }
+
+        fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+            Ok(bytes.to_vec())
+        }
+
+        fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(bytes)
+        }
+
+        // Bytes written as an array of integers, accepted by `ciborium` for example
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(
+            self,
+            mut seq: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! Manage keys, sign and verify json payloads from a terminal
+//!
+//! Keys and signatures are read from files, or from stdin when the path is missing or `-`, and
+//! written on stdout in json.
+//!
+//! ```console
+//! $ sigserlic keygen --comment "release key" --expiration 1y > key.json
+//! $ sigserlic pubkey key.json > public_key.json
+//! $ echo '{"name": "Toto", "seats": 5}' | sigserlic sign --key key.json > license.json
+//! $ sigserlic verify --key public_key.json license.json
+//! ```
+
+use base64ct::Encoding;
+use clap::{Parser, Subcommand};
+use jiff::tz::TimeZone;
+use jiff::{Span, Timestamp};
+use serde_json::Value;
+use sigserlic::error::{DependencyError, SignatureBuilderError, SignatureError, TimestampError};
+use sigserlic::{KeyMetadata, PublicKey, Signature, SignatureBuilder, SigningKey};
+use snafu::{ResultExt, Snafu};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Comments of keys and signatures made by the tool
+type Comment = String;
+
+#[derive(Parser)]
+#[command(version, about)]
+#[command(
+    after_help = "Exit codes of `verify`: 0 valid, 1 error or invalid arguments, 2 bad signature, 3 wrong key, 4 expired"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a signing key
+    Keygen {
+        /// Comment of the key
+        #[arg(short, long)]
+        comment: Option<Comment>,
+        /// When the key expires: a timestamp (`2030-01-01T00:00:00Z`) or a duration from now (`1y`)
+        #[arg(short, long, value_parser = parse_when)]
+        expiration: Option<Timestamp>,
+    },
+    /// Extract the public key of a signing key
+    Pubkey {
+        /// Signing key in json
+        key: Option<PathBuf>,
+    },
+    /// Sign a json payload
+    Sign {
+        /// Signing key in json
+        #[arg(short, long)]
+        key: PathBuf,
+        /// Untrusted comment of the signature
+        #[arg(short, long)]
+        comment: Option<Comment>,
+        /// When the signature expires: a timestamp (`2030-01-01T00:00:00Z`) or a duration from now
+        /// (`30d`)
+        #[arg(short, long, value_parser = parse_when)]
+        expiration: Option<Timestamp>,
+        /// Payload in json
+        payload: Option<PathBuf>,
+    },
+    /// Verify a signature, and print its payload
+    Verify {
+        /// Public key in json
+        #[arg(short, long)]
+        key: PathBuf,
+        /// Signature in json
+        signature: Option<PathBuf>,
+    },
+    /// Show what a key or a signature contains, without verifying anything
+    Inspect {
+        /// Key or signature in json
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Snafu)]
+enum CliError {
+    #[snafu(display("reading `{}`", path.display()))]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("json"))]
+    Json { source: serde_json::Error },
+    #[snafu(display("invalid expiration"))]
+    Expiration { source: TimestampError },
+    #[snafu(display("signing payload"))]
+    Sign { source: SignatureBuilderError },
+    #[snafu(display("not a key nor a signature"))]
+    Unknown,
+    #[snafu(display("signature is not authentic"))]
+    BadSignature { source: SignatureError },
+    #[snafu(display("signature was made by another key"))]
+    WrongKey { source: SignatureError },
+    #[snafu(display("signature expired on {expiration}"))]
+    Expired { expiration: Timestamp },
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Self::BadSignature { .. } => 2,
+            Self::WrongKey { .. } => 3,
+            Self::Expired { .. } => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// Parse a timestamp, or a duration from now
+fn parse_when(when: &str) -> Result<Timestamp, String> {
+    if let Ok(timestamp) = when.parse::<Timestamp>() {
+        return Ok(timestamp);
+    }
+
+    let span: Span = when.parse().map_err(|e| format!("{e}"))?;
+    Timestamp::now()
+        .to_zoned(TimeZone::UTC)
+        .checked_add(span)
+        .map(|zoned| zoned.timestamp())
+        .map_err(|e| format!("{e}"))
+}
+
+/// Read a file, or stdin if there is no path or it is `-`
+fn read(path: Option<&Path>) -> Result<String, CliError> {
+    let mut content = String::new();
+    match path {
+        Some(path) if path != Path::new("-") => {
+            content = std::fs::read_to_string(path).context(ReadSnafu { path })?;
+        }
+        _ => {
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .context(ReadSnafu { path: "stdin" })?;
+        }
+    }
+    Ok(content)
+}
+
+fn keygen(comment: Option<Comment>, expiration: Option<Timestamp>) -> Result<String, CliError> {
+    let mut key = SigningKey::<Comment>::generate();
+    if let Some(comment) = comment {
+        key = key.with_comment(comment);
+    }
+    if let Some(expiration) = expiration {
+        key = key
+            .with_expiration(expiration.as_second())
+            .context(ExpirationSnafu)?;
+    }
+    serde_json::to_string_pretty(&key).context(JsonSnafu)
+}
+
+fn pubkey(key: &str) -> Result<String, CliError> {
+    let key: SigningKey<Value> = serde_json::from_str(key).context(JsonSnafu)?;
+    serde_json::to_string_pretty(&PublicKey::from(key)).context(JsonSnafu)
+}
+
+fn sign(
+    key: &str,
+    payload: &str,
+    comment: Option<Comment>,
+    expiration: Option<Timestamp>,
+) -> Result<String, CliError> {
+    let key: SigningKey<Value> = serde_json::from_str(key).context(JsonSnafu)?;
+    let payload: Value = serde_json::from_str(payload).context(JsonSnafu)?;
+
+    let mut builder = SignatureBuilder::<Value, Comment>::new(payload);
+    if let Some(comment) = comment {
+        builder = builder.comment(comment);
+    }
+    if let Some(expiration) = expiration {
+        builder = builder
+            .expiration(expiration.as_second())
+            .context(ExpirationSnafu)?;
+    }
+    let signature = key.sign(builder).context(SignSnafu)?;
+    serde_json::to_string_pretty(&signature).context(JsonSnafu)
+}
+
+fn verify(key: &str, signature: &str, now: Timestamp) -> Result<String, CliError> {
+    let key: PublicKey<Value> = serde_json::from_str(key).context(JsonSnafu)?;
+    let signature: Signature<Value, Value> = serde_json::from_str(signature).context(JsonSnafu)?;
+
+    let message = signature.verify(&key).map_err(|source| match source {
+        SignatureError::Verify {
+            source: DependencyError(libsignify::Error::MismatchedKey { .. }),
+        } => CliError::WrongKey { source },
+        source => CliError::BadSignature { source },
+    })?;
+    if let Some(expiration) = message.expiration()
+        && message.is_expired_at(now)
+    {
+        return Err(CliError::Expired { expiration });
+    }
+
+    serde_json::to_string_pretty(message.data()).context(JsonSnafu)
+}
+
+/// Describe the metadata of a key
+fn describe_key<C: serde::Serialize>(kind: &str, key: &impl KeyMetadata<C>) -> String {
+    let timestamp = |seconds| Timestamp::from_second(seconds).expect("valid timestamp");
+    let mut description = format!(
+        "{kind}\nkey number: {}\ncreated at: {}\nexpires at: {}\n",
+        base64ct::Base64::encode_string(key.keynum().as_ref()),
+        timestamp(key.created_at()),
+        key.expired_at()
+            .map_or("never".into(), |e| timestamp(e).to_string()),
+    );
+    if let Some(comment) = key.comment() {
+        let comment = serde_json::to_string(comment).expect("comment was json");
+        description.push_str(&format!("comment: {comment}\n"));
+    }
+    description
+}
+
+fn inspect(content: &str) -> Result<String, CliError> {
+    let value: Value = serde_json::from_str(content).context(JsonSnafu)?;
+
+    if value.get("secret_key").is_some() {
+        let key: SigningKey<Value> = serde_json::from_value(value).context(JsonSnafu)?;
+        Ok(describe_key("signing key", &key))
+    } else if value.get("public_key").is_some() {
+        let key: PublicKey<Value> = serde_json::from_value(value).context(JsonSnafu)?;
+        Ok(describe_key("public key", &key))
+    } else if let Some(artifact) = value.get("signed_artifact").cloned() {
+        let signature: Signature<Value, Value> =
+            serde_json::from_value(value).context(JsonSnafu)?;
+        let signer = signature
+            .signature()
+            .context(BadSignatureSnafu)?
+            .signer_keynum();
+
+        let mut description = format!(
+            "signature (not verified)\nsigned by key: {}\nsigned at: {}\nexpires at: {}\n",
+            base64ct::Base64::encode_string(signer.as_ref()),
+            artifact["timestamp"].as_str().unwrap_or_default(),
+            artifact["expiration"].as_str().unwrap_or("never"),
+        );
+        if let Some(nonce) = artifact.get("nonce").and_then(Value::as_str) {
+            description.push_str(&format!("nonce: {nonce}\n"));
+        }
+        if let Some(comment) = signature.comment() {
+            description.push_str(&format!("comment: {comment}\n"));
+        }
+        let data = serde_json::to_string_pretty(&artifact["data"]).context(JsonSnafu)?;
+        description.push_str(&format!("data: {data}\n"));
+        Ok(description)
+    } else {
+        Err(CliError::Unknown)
+    }
+}
+
+fn run(command: Command) -> Result<String, CliError> {
+    match command {
+        Command::Keygen {
+            comment,
+            expiration,
+        } => keygen(comment, expiration),
+        Command::Pubkey { key } => pubkey(&read(key.as_deref())?),
+        Command::Sign {
+            key,
+            comment,
+            expiration,
+            payload,
+        } => sign(
+            &read(Some(&key))?,
+            &read(payload.as_deref())?,
+            comment,
+            expiration,
+        ),
+        Command::Verify { key, signature } => verify(
+            &read(Some(&key))?,
+            &read(signature.as_deref())?,
+            Timestamp::now(),
+        ),
+        Command::Inspect { file } => inspect(&read(file.as_deref())?),
+    }
+}
+
+/// Exit code of a command line clap could not parse: 1 instead of clap's 2, which is a bad
+/// signature, and 0 for `--help` and `--version`
+fn usage_exit_code(error: &clap::Error) -> u8 {
+    if error.use_stderr() { 1 } else { 0 }
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::from(usage_exit_code(&e));
+        }
+    };
+    match run(cli.command) {
+        Ok(output) => {
+            println!("{}", output.trim_end());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {}", snafu::Report::from_error(&e));
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNING_KEY_JSON: &str = r#"{"secret_key":"RWRCSwAAAAD7Od0ms9qjK7pDPi1+07phkG3M+2u/tP+Xrjfqh35YjNsnWGP4FPXiY52Ai99W3A0UKrt65iZ9bYhInAZx63D4dopB2KUGoLLQLZtDMySVeFow8Zp/0X9465QjzovIsCY=","created_at":"2024-12-23T00:12:54.53753Z","expired_at":null,"comment":"testing key"}"#;
+    const PUBLIC_KEY2_JSON: &str = r#"{"public_key":"RWQnJUuDaiWf2BAemrs2IhCFqOIW6Ivotz6JpSAq4ch55WC/rz/fq0mY","created_at":"2024-12-23T16:39:25.85933Z","expired_at":null}"#;
+
+    fn now() -> Timestamp {
+        Timestamp::from_second(1800000000).unwrap()
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let public_key = pubkey(SIGNING_KEY_JSON).unwrap();
+        let signature = sign(SIGNING_KEY_JSON, r#"{"seats": 5}"#, None, None).unwrap();
+
+        let payload = verify(&public_key, &signature, now()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&payload).unwrap(),
+            serde_json::json!({"seats": 5})
+        );
+    }
+
+    #[test]
+    fn exit_codes() {
+        let public_key = pubkey(SIGNING_KEY_JSON).unwrap();
+        let expiration = Some(now());
+        let signature = sign(SIGNING_KEY_JSON, "5", None, expiration).unwrap();
+
+        let altered = signature.replace("\"data\": 5", "\"data\": 50");
+        assert_eq!(
+            verify(&public_key, &altered, now())
+                .unwrap_err()
+                .exit_code(),
+            2
+        );
+        assert_eq!(
+            verify(PUBLIC_KEY2_JSON, &signature, now())
+                .unwrap_err()
+                .exit_code(),
+            3
+        );
+        assert_eq!(
+            verify(&public_key, &signature, now())
+                .unwrap_err()
+                .exit_code(),
+            4
+        );
+        assert_eq!(verify(&public_key, "{", now()).unwrap_err().exit_code(), 1);
+    }
+
+    #[test]
+    fn usage_exit_codes() {
+        let exit_code = |args: &[&str]| {
+            let args = std::iter::once("sigserlic").chain(args.iter().copied());
+            usage_exit_code(&Cli::try_parse_from(args).err().unwrap())
+        };
+
+        // Missing `--key`, must not look like a bad signature
+        assert_eq!(exit_code(&["verify", "signature.json"]), 1);
+        assert_eq!(exit_code(&["toto"]), 1);
+        assert_eq!(exit_code(&["--help"]), 0);
+        assert_eq!(exit_code(&["--version"]), 0);
+    }
+
+    #[test]
+    fn inspect_everything() {
+        let signing_key = inspect(SIGNING_KEY_JSON).unwrap();
+        assert!(signing_key.starts_with("signing key\nkey number: l6436od+WIw=\n"));
+        assert!(!signing_key.contains("RWRCSwAAAAD7"));
+        assert!(signing_key.ends_with("comment: \"testing key\"\n"));
+
+        let public_key = inspect(&pubkey(SIGNING_KEY_JSON).unwrap()).unwrap();
+        assert!(public_key.starts_with("public key\nkey number: l6436od+WIw=\n"));
+
+        let signature = sign(SIGNING_KEY_JSON, "[1, 2]", Some("toto".into()), None).unwrap();
+        let signature = inspect(&signature).unwrap();
+        assert!(signature.contains("signed by key: l6436od+WIw=\n"));
+        assert!(signature.contains("expires at: never\n"));
+        assert!(signature.contains("comment: \"toto\"\n"));
+
+        assert!(matches!(inspect("{}").unwrap_err(), CliError::Unknown));
+    }
+
+    #[test]
+    fn when() {
+        assert_eq!(
+            parse_when("2030-01-01T00:00:00Z").unwrap(),
+            Timestamp::from_second(1893456000).unwrap()
+        );
+        assert!(parse_when("1y").unwrap() > Timestamp::now());
+        assert!(parse_when("toto").is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Detect system clocks moved backwards, to keep expiration meaningful offline
+//!
+//! A [`LastSeenStore`] remembers the latest time observed by the program in a small file,
+//! authenticated with a MAC. When checking an expiration, the time used is the maximum of the
+//! system time and of the last seen time: winding back the clock does not make an expired
+//! [`Message`] valid again.
+//!
+//! ```
+//! use sigserlic::clock::LastSeenStore;
+//! # let path = std::env::temp_dir().join(format!("sigserlic-doc-{}", std::process::id()));
+//!
+//! let store = LastSeenStore::new(&path, b"secret specific to the installation");
+//! let time = store.observe().unwrap();
+//! assert!(!time.is_rolled_back());
+//! # std::fs::remove_file(path).unwrap();
+//! ```
+
+use crate::Message;
+use base64ct::Encoding;
+use hmac::{Hmac, Mac};
+use jiff::{SignedDuration, Timestamp};
+use sha2::Sha256;
+use snafu::{ResultExt, Snafu};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Counter making temporary files of a process unique
+static TEMPORARY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Unique file next to `path` to write before renaming it, which cannot be the file of another
+/// program nor the temporary file of a concurrent writer
+pub(crate) fn temporary_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let counter = TEMPORARY_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{name}.{}.{counter}.tmp", std::process::id()))
+}
+
+/// File storing the last time seen by the program, authenticated with a MAC
+///
+/// The MAC only prevents forging a later time. Deleting the file looks like a first use, and
+/// putting back an older copy of it is accepted: both reset the last seen time. Check
+/// [`TrustedTime::is_first_use()`] and decide what a missing store means for your program, for
+/// example by refusing it once a license has been activated, or keep a copy of the store in a
+/// location the user does not control.
+pub struct LastSeenStore {
+    path: PathBuf,
+    key: Vec<u8>,
+}
+
+impl LastSeenStore {
+    /// Store located at `path`, authenticated with `key`
+    pub fn new(path: impl Into<PathBuf>, key: &[u8]) -> Self {
+        Self {
+            path: path.into(),
+            key: key.to_vec(),
+        }
+    }
+
+    /// Location of the store
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn mac(&self, timestamp: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(timestamp.as_bytes());
+        mac
+    }
+
+    /// Read the last seen time, `None` if nothing was stored yet
+    pub fn load(&self) -> Result<Option<Timestamp>, ClockError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).context(IoSnafu { path: &self.path }),
+        };
+
+        let (timestamp, tag) = content
+            .trim()
+            .split_once(' ')
+            .ok_or(ClockError::Corrupted)?;
+        let tag = base64ct::Base64::decode_vec(tag).map_err(|_| ClockError::Corrupted)?;
+        self.mac(timestamp)
+            .verify_slice(&tag)
+            .map_err(|_| ClockError::Tampered)?;
+
+        Timestamp::from_str(timestamp)
+            .map(Some)
+            .map_err(|_| ClockError::Corrupted)
+    }
+
+    /// Overwrite the last seen time
+    pub fn store(&self, timestamp: Timestamp) -> Result<(), ClockError> {
+        let timestamp = timestamp.to_string();
+        let tag = self.mac(&timestamp).finalize().into_bytes();
+        let content = format!("{timestamp} {}\n", base64ct::Base64::encode_string(&tag));
+
+        // Write to a temporary file first, a crash must not leave a truncated store
+        let temporary = temporary_path(&self.path);
+        std::fs::write(&temporary, content).context(IoSnafu { path: &temporary })?;
+        std::fs::rename(&temporary, &self.path).context(IoSnafu { path: &self.path })
+    }
+
+    /// Compare the current system time with the last seen time, and remember the latest of both
+    pub fn observe(&self) -> Result<TrustedTime, ClockError> {
+        self.observe_at(Timestamp::now())
+    }
+
+    /// Same as [`LastSeenStore::observe()`], with a provided system time
+    pub fn observe_at(&self, system: Timestamp) -> Result<TrustedTime, ClockError> {
+        let time = TrustedTime {
+            system,
+            last_seen: self.load()?,
+        };
+        if time.last_seen.is_none_or(|last_seen| system > last_seen) {
+            self.store(system)?;
+        }
+        Ok(time)
+    }
+
+    /// Observe time, and make sure `message` has not expired
+    ///
+    /// The returned [`TrustedTime`] tells if the clock appears to have moved backwards.
+    pub fn check_expiration<T>(&self, message: &Message<T>) -> Result<TrustedTime, ClockError> {
+        let time = self.observe()?;
+        time.check_expiration(message)?;
+        Ok(time)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Time observed by a [`LastSeenStore`]
+pub struct TrustedTime {
+    system: Timestamp,
+    last_seen: Option<Timestamp>,
+}
+
+impl TrustedTime {
+    /// Latest time known: the maximum of the system time and of the last seen time
+    pub fn now(&self) -> Timestamp {
+        self.last_seen
+            .map_or(self.system, |last_seen| last_seen.max(self.system))
+    }
+
+    /// Current time, according to the system clock
+    pub fn system(&self) -> Timestamp {
+        self.system
+    }
+
+    /// Time previously stored, if any
+    pub fn last_seen(&self) -> Option<Timestamp> {
+        self.last_seen
+    }
+
+    /// Nothing was stored yet: this is the first use, or the store has been deleted
+    pub fn is_first_use(&self) -> bool {
+        self.last_seen.is_none()
+    }
+
+    /// The system clock is behind a time already seen
+    pub fn is_rolled_back(&self) -> bool {
+        self.rollback().is_some()
+    }
+
+    /// How far the system clock went backwards, if it did
+    pub fn rollback(&self) -> Option<SignedDuration> {
+        self.last_seen
+            .filter(|last_seen| *last_seen > self.system)
+            .map(|last_seen| last_seen.duration_since(self.system))
+    }
+
+    /// Make sure `message` has not expired at this time
+    pub fn check_expiration<T>(&self, message: &Message<T>) -> Result<(), ClockError> {
+        let now = self.now();
+        match message.expiration() {
+            Some(expiration) if message.is_expired_at(now) => {
+                Err(ClockError::Expired { expiration, now })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when checking time against a [`LastSeenStore`]
+pub enum ClockError {
+    #[snafu(display("accessing last seen time at {}", path.display()))]
+    /// Failed to read or write the store
+    Io {
+        /// Location of the store
+        path: PathBuf,
+        /// Original error
+        source: std::io::Error,
+    },
+    #[snafu(display("last seen time is not readable"))]
+    /// Content of the store is not in the expected format
+    Corrupted,
+    #[snafu(display("last seen time has been tampered with"))]
+    /// The MAC of the store does not match its content
+    Tampered,
+    #[snafu(display("expired at {expiration}, now is {now}"))]
+    /// Message is expired
+    Expired {
+        /// Expiration of the message
+        expiration: Timestamp,
+        /// Time used for the check
+        now: Timestamp,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"toto mange du gateau";
+    const TIMESTAMP_1: i64 = 1700000000;
+    const TIMESTAMP_2: i64 = 1800000000;
+    const TIMESTAMP_3: i64 = 1900000000;
+
+    struct TemporaryStore(LastSeenStore);
+
+    impl TemporaryStore {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("sigserlic-clock-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            Self(LastSeenStore::new(path, KEY))
+        }
+    }
+
+    impl Drop for TemporaryStore {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0.path);
+        }
+    }
+
+    fn timestamp(second: i64) -> Timestamp {
+        Timestamp::from_second(second).unwrap()
+    }
+
+    fn message(expiration: i64) -> Message<()> {
+        serde_json::from_str(&format!(
+            r#"{{"data":null,"timestamp":"{}","expiration":"{}"}}"#,
+            timestamp(TIMESTAMP_1),
+            timestamp(expiration)
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn empty_store() {
+        let store = TemporaryStore::new("empty");
+        assert_eq!(store.0.load().unwrap(), None);
+
+        let time = store.0.observe_at(timestamp(TIMESTAMP_1)).unwrap();
+        assert_eq!(time.now(), timestamp(TIMESTAMP_1));
+        assert!(time.is_first_use());
+        assert!(!time.is_rolled_back());
+        assert_eq!(store.0.load().unwrap(), Some(timestamp(TIMESTAMP_1)));
+    }
+
+    #[test]
+    fn deleted_store() {
+        let store = TemporaryStore::new("deleted");
+        store.0.observe_at(timestamp(TIMESTAMP_3)).unwrap();
+        std::fs::remove_file(store.0.path()).unwrap();
+
+        let time = store.0.observe_at(timestamp(TIMESTAMP_1)).unwrap();
+        assert!(time.is_first_use());
+        assert!(!time.is_rolled_back());
+    }
+
+    #[test]
+    fn sibling_file_kept() {
+        let store = TemporaryStore::new("sibling");
+        let sibling = store.0.path().with_extension("tmp");
+        std::fs::write(&sibling, "toto mange du gateau").unwrap();
+
+        store.0.store(timestamp(TIMESTAMP_1)).unwrap();
+        let content = std::fs::read_to_string(&sibling);
+        std::fs::remove_file(&sibling).unwrap();
+        assert_eq!(content.unwrap(), "toto mange du gateau");
+    }
+
+    #[test]
+    fn clock_moving_forward() {
+        let store = TemporaryStore::new("forward");
+        store.0.observe_at(timestamp(TIMESTAMP_1)).unwrap();
+
+        let time = store.0.observe_at(timestamp(TIMESTAMP_2)).unwrap();
+        assert_eq!(time.now(), timestamp(TIMESTAMP_2));
+        assert!(!time.is_first_use());
+        assert!(!time.is_rolled_back());
+        assert_eq!(store.0.load().unwrap(), Some(timestamp(TIMESTAMP_2)));
+    }
+
+    #[test]
+    fn clock_rolled_back() {
+        let store = TemporaryStore::new("rollback");
+        store.0.observe_at(timestamp(TIMESTAMP_3)).unwrap();
+
+        let time = store.0.observe_at(timestamp(TIMESTAMP_1)).unwrap();
+        assert_eq!(time.now(), timestamp(TIMESTAMP_3));
+        assert_eq!(
+            time.rollback(),
+            Some(SignedDuration::from_secs(TIMESTAMP_3 - TIMESTAMP_1))
+        );
+        assert_eq!(store.0.load().unwrap(), Some(timestamp(TIMESTAMP_3)));
+
+        // Expired according to last seen time, even if system clock says otherwise
+        assert!(matches!(
+            time.check_expiration(&message(TIMESTAMP_2)).unwrap_err(),
+            ClockError::Expired { .. }
+        ));
+    }
+
+    #[test]
+    fn not_expired() {
+        let store = TemporaryStore::new("valid");
+        let time = store.0.observe_at(timestamp(TIMESTAMP_2)).unwrap();
+        assert!(time.check_expiration(&message(TIMESTAMP_3)).is_ok());
+    }
+
+    #[test]
+    fn tampered_store() {
+        let store = TemporaryStore::new("tampered");
+        store.0.store(timestamp(TIMESTAMP_3)).unwrap();
+
+        let content = std::fs::read_to_string(store.0.path()).unwrap();
+        let content = content.replace(
+            &timestamp(TIMESTAMP_3).to_string(),
+            &timestamp(TIMESTAMP_1).to_string(),
+        );
+        std::fs::write(store.0.path(), content).unwrap();
+        assert!(matches!(store.0.load().unwrap_err(), ClockError::Tampered));
+
+        let other_key = LastSeenStore::new(store.0.path(), b"another key");
+        store.0.store(timestamp(TIMESTAMP_3)).unwrap();
+        assert!(matches!(
+            other_key.load().unwrap_err(),
+            ClockError::Tampered
+        ));
+    }
+
+    #[test]
+    fn corrupted_store() {
+        let store = TemporaryStore::new("corrupted");
+        std::fs::write(store.0.path(), "toto mange du gateau").unwrap();
+        assert!(matches!(store.0.load().unwrap_err(), ClockError::Corrupted));
+    }
+}
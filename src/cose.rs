@@ -0,0 +1,407 @@
+//! Export signatures as `COSE_Sign1` structures ([RFC 9052](https://www.rfc-editor.org/rfc/rfc9052)),
+//! for constrained devices speaking CBOR
+//!
+//! The data of the [`Message`] is encoded in CBOR as the payload. The protected headers contain
+//! the `EdDSA` algorithm and the timestamps as CWT claims ([RFC 9597](https://www.rfc-editor.org/rfc/rfc9597)):
+//! `iat` for the timestamp, `exp` for the expiration and `cti` for the nonce. The unprotected
+//! headers contain the key number as `kid`, and the comment under the `"comment"` label.
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//! type MySignatureBuilder = sigserlic::SignatureBuilder::<Vec<u8>, ()>;
+//!
+//! let builder = MySignatureBuilder::new(vec![0xde, 0xad, 0xba, 0xed]);
+//! let signature = signing_key.sign(builder).unwrap();
+//!
+//! let cose = signature.to_cose_sign1(&signing_key).unwrap();
+//!
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//! let (message, _) = public_key.verify_cose_sign1::<Vec<u8>, ()>(&cose).unwrap();
+//! assert_eq!(message.data(), &[0xde, 0xad, 0xba, 0xed]);
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::error::{SignatureError, TimestampError};
+use crate::{KeyMetadata, Message, Nonce, PublicKey, Signature};
+use ciborium::Value;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use snafu::{ResultExt, Snafu};
+
+/// CBOR tag of `COSE_Sign1`
+const TAG: u64 = 18;
+
+/// Header labels
+const ALG: i64 = 1;
+const KID: i64 = 4;
+const CWT_CLAIMS: i64 = 15;
+const COMMENT: &str = "comment";
+
+/// `EdDSA` algorithm identifier
+const EDDSA: i64 = -8;
+
+/// CWT claims labels
+const EXP: i64 = 4;
+const IAT: i64 = 6;
+const CTI: i64 = 7;
+
+#[derive(Debug, Snafu)]
+/// Errors when producing or verifying a `COSE_Sign1` structure
+pub enum CoseError {
+    #[snafu(display("encoding or decoding cbor"))]
+    /// Failed to encode or decode CBOR
+    Cbor,
+    #[snafu(display("not a COSE_Sign1 structure"))]
+    /// Valid CBOR, but missing fields of a `COSE_Sign1` structure, or of the timestamp claims
+    Format,
+    #[snafu(display("not signed with EdDSA"))]
+    /// Signature made with another algorithm
+    Algorithm,
+    #[snafu(display("signature was made by another key"))]
+    /// Key identifier is not the one of the key
+    KeyMismatch,
+    #[snafu(display("decoding signature"))]
+    /// The signature to convert could not be decoded
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+    #[snafu(display("invalid timestamp in claims"))]
+    /// `iat` or `exp` claim is out of range
+    Timestamp {
+        /// Original error
+        source: TimestampError,
+    },
+    #[snafu(display("verify signature with public key"))]
+    /// Failed to use key to verify authenticity of structure
+    Verify {
+        /// Original error
+        source: libsignify::Error,
+    },
+}
+
+fn to_cbor<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CoseError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|_| CoseError::Cbor)?;
+    Ok(bytes)
+}
+
+/// Decode a single CBOR item, with nothing after it
+fn from_cbor<T: DeserializeOwned>(mut bytes: &[u8]) -> Result<T, CoseError> {
+    let value = ciborium::from_reader(&mut bytes).map_err(|_| CoseError::Cbor)?;
+    match bytes.is_empty() {
+        true => Ok(value),
+        false => Err(CoseError::Cbor),
+    }
+}
+
+fn get(map: &[(Value, Value)], label: i64) -> Option<&Value> {
+    map.iter()
+        .find(|(key, _)| *key == Value::Integer(label.into()))
+        .map(|(_, value)| value)
+}
+
+fn get_integer(map: &[(Value, Value)], label: i64) -> Result<Option<i64>, CoseError> {
+    get(map, label)
+        .map(|value| {
+            value
+                .as_integer()
+                .and_then(|integer| i64::try_from(integer).ok())
+                .ok_or(CoseError::Format)
+        })
+        .transpose()
+}
+
+/// Data covered by the signature, `Sig_structure` of RFC 9052
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Result<Vec<u8>, CoseError> {
+    to_cbor(&Value::Array(vec![
+        Value::Text("Signature1".into()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(Vec::new()),
+        Value::Bytes(payload.to_vec()),
+    ]))
+}
+
+impl<T: Serialize, C: Serialize> Signature<T, C> {
+    #[cfg(feature = "sign")]
+    /// Convert the signed message to a tagged `COSE_Sign1` structure
+    ///
+    /// COSE carries its own signature over the protected headers, made by `signing_key`: it is
+    /// rejected if it did not sign the message. Timestamps are truncated to seconds.
+    pub fn to_cose_sign1<S>(&self, signing_key: &SigningKey<S>) -> Result<Vec<u8>, CoseError> {
+        let signer = self.signature().context(SignatureSnafu)?.signer_keynum();
+        if signer != signing_key.keynum() {
+            return Err(CoseError::KeyMismatch);
+        }
+
+        let message = &self.signed_artifact;
+        let mut claims = vec![(IAT.into(), message.timestamp.as_second().into())];
+        if let Some(expiration) = message.expiration {
+            claims.push((EXP.into(), expiration.as_second().into()));
+        }
+        if let Some(nonce) = message.nonce {
+            claims.push((CTI.into(), Value::Bytes(nonce.as_bytes().to_vec())));
+        }
+        let protected = to_cbor(&Value::Map(vec![
+            (ALG.into(), EDDSA.into()),
+            (CWT_CLAIMS.into(), Value::Map(claims)),
+        ]))?;
+
+        let mut unprotected = vec![(KID.into(), Value::Bytes(signer.as_ref().to_vec()))];
+        if let Some(comment) = self.comment() {
+            let comment = Value::serialized(comment).map_err(|_| CoseError::Cbor)?;
+            unprotected.push((COMMENT.into(), comment));
+        }
+
+        let payload = to_cbor(&message.data)?;
+        let signature = signing_key.sign_raw(&sig_structure(&protected, &payload)?);
+
+        to_cbor(&Value::Tag(
+            TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(protected),
+                Value::Map(unprotected),
+                Value::Bytes(payload),
+                Value::Bytes(signature.to_vec()),
+            ])),
+        ))
+    }
+}
+
+impl<CPubKey> PublicKey<CPubKey> {
+    /// Verify a `COSE_Sign1` structure, tagged or not, signed with `EdDSA` by the signing key of
+    /// this public key, and get the message out of it, with the comment
+    ///
+    /// The expiration found in the protected headers is only decoded, use
+    /// [`Message::is_expired_at()`] on the returned message to enforce it.
+    pub fn verify_cose_sign1<T: DeserializeOwned, C: DeserializeOwned>(
+        &self,
+        cose: &[u8],
+    ) -> Result<(Message<T>, Option<C>), CoseError> {
+        let value: Value = from_cbor(cose)?;
+        let value = match value {
+            Value::Tag(TAG, value) => *value,
+            value => value,
+        };
+        let Ok([protected, unprotected, payload, signature]) =
+            <[Value; 4]>::try_from(value.into_array().map_err(|_| CoseError::Format)?)
+        else {
+            return Err(CoseError::Format);
+        };
+        let (
+            Value::Bytes(protected),
+            Value::Map(unprotected),
+            Value::Bytes(payload),
+            Value::Bytes(signature),
+        ) = (protected, unprotected, payload, signature)
+        else {
+            return Err(CoseError::Format);
+        };
+
+        let headers: Value = from_cbor(&protected)?;
+        let headers = headers.as_map().ok_or(CoseError::Format)?;
+        if get_integer(headers, ALG)? != Some(EDDSA) {
+            return Err(CoseError::Algorithm);
+        }
+        if let Some(kid) = get(&unprotected, KID)
+            && kid.as_bytes().map(Vec::as_slice) != Some(self.keynum().as_ref())
+        {
+            return Err(CoseError::KeyMismatch);
+        }
+
+        self.verify_raw(&sig_structure(&protected, &payload)?, &signature)
+            .context(VerifySnafu)?;
+
+        let claims = get(headers, CWT_CLAIMS)
+            .and_then(Value::as_map)
+            .ok_or(CoseError::Format)?;
+        let timestamp = get_integer(claims, IAT)?.ok_or(CoseError::Format)?;
+        let expiration = get_integer(claims, EXP)?;
+        let nonce = get(claims, CTI)
+            .map(|cti| {
+                let bytes: [u8; Nonce::LEN] = cti
+                    .as_bytes()
+                    .and_then(|bytes| bytes.as_slice().try_into().ok())
+                    .ok_or(CoseError::Format)?;
+                Ok(Nonce::from(bytes))
+            })
+            .transpose()?;
+
+        let comment = unprotected
+            .iter()
+            .find(|(key, _)| key.as_text() == Some(COMMENT))
+            .map(|(_, comment)| comment.deserialized().map_err(|_| CoseError::Cbor))
+            .transpose()?;
+
+        let message = Message {
+            data: from_cbor(&payload)?,
+            timestamp: crate::timestamp::parse_timestamp(timestamp).context(TimestampSnafu)?,
+            expiration: expiration
+                .map(crate::timestamp::parse_timestamp)
+                .transpose()
+                .context(TimestampSnafu)?,
+            nonce,
+        };
+        Ok((message, comment))
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::SignatureBuilder;
+    use crate::test_keys::{other_public_key, public_key, signing_key};
+
+    fn signature() -> Signature<String, String> {
+        let builder = SignatureBuilder::new("toto mange du gateau".into())
+            .timestamp(1700000000)
+            .unwrap()
+            .expiration(1800000000)
+            .unwrap()
+            .nonce(Nonce::from([7; Nonce::LEN]))
+            .comment("toto".into());
+        signing_key().sign(builder).unwrap()
+    }
+
+    #[test]
+    fn structure() {
+        let cose = signature().to_cose_sign1(&signing_key()).unwrap();
+        let value: Value = from_cbor(&cose).unwrap();
+        let (tag, value) = value.as_tag().unwrap();
+        assert_eq!(tag, TAG);
+
+        let fields = value.as_array().unwrap();
+        let protected: Value = from_cbor(fields[0].as_bytes().unwrap()).unwrap();
+        assert_eq!(
+            protected,
+            Value::Map(vec![
+                (1.into(), (-8).into()),
+                (
+                    15.into(),
+                    Value::Map(vec![
+                        (6.into(), 1700000000.into()),
+                        (4.into(), 1800000000.into()),
+                        (7.into(), Value::Bytes(vec![7; 16])),
+                    ])
+                ),
+            ])
+        );
+        assert_eq!(
+            fields[1],
+            Value::Map(vec![
+                (
+                    4.into(),
+                    Value::Bytes(vec![0x97, 0xae, 0x37, 0xea, 0x87, 0x7e, 0x58, 0x8c])
+                ),
+                ("comment".into(), "toto".into()),
+            ])
+        );
+        assert_eq!(
+            from_cbor::<String>(fields[2].as_bytes().unwrap()).unwrap(),
+            "toto mange du gateau"
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let cose = signature().to_cose_sign1(&signing_key()).unwrap();
+        let (message, comment) = public_key()
+            .verify_cose_sign1::<String, String>(&cose)
+            .unwrap();
+
+        assert_eq!(message, signature().verify(&signing_key().into()).unwrap());
+        assert_eq!(comment.as_deref(), Some("toto"));
+    }
+
+    #[test]
+    fn wrong_key() {
+        let cose = signature().to_cose_sign1(&signing_key()).unwrap();
+        let other = other_public_key();
+
+        assert!(matches!(
+            other
+                .verify_cose_sign1::<String, String>(&cose)
+                .unwrap_err(),
+            CoseError::KeyMismatch
+        ));
+    }
+
+    #[test]
+    fn altered_payload() {
+        let cose = signature().to_cose_sign1(&signing_key()).unwrap();
+        let mut value: Value = from_cbor(&cose).unwrap();
+        let (_, inner) = value.as_tag_mut().unwrap();
+        inner.as_array_mut().unwrap()[2] = Value::Bytes(to_cbor("toto mange du pain").unwrap());
+
+        assert!(matches!(
+            public_key()
+                .verify_cose_sign1::<String, String>(&to_cbor(&value).unwrap())
+                .unwrap_err(),
+            CoseError::Verify { .. }
+        ));
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut cose = signature().to_cose_sign1(&signing_key()).unwrap();
+        cose.push(0);
+        assert!(matches!(
+            public_key()
+                .verify_cose_sign1::<String, String>(&cose)
+                .unwrap_err(),
+            CoseError::Cbor
+        ));
+
+        // Payload followed by a byte, signed again
+        let cose = signature().to_cose_sign1(&signing_key()).unwrap();
+        let mut value: Value = from_cbor(&cose).unwrap();
+        let (_, inner) = value.as_tag_mut().unwrap();
+        let inner = inner.as_array_mut().unwrap();
+        let mut payload = inner[2].as_bytes().unwrap().clone();
+        payload.push(0);
+        let protected = inner[0].as_bytes().unwrap().clone();
+        let signature = signing_key().sign_raw(&sig_structure(&protected, &payload).unwrap());
+        inner[2] = Value::Bytes(payload);
+        inner[3] = Value::Bytes(signature.to_vec());
+
+        assert!(matches!(
+            public_key()
+                .verify_cose_sign1::<String, String>(&to_cbor(&value).unwrap())
+                .unwrap_err(),
+            CoseError::Cbor
+        ));
+    }
+
+    #[test]
+    fn invalid_structure() {
+        let public_key = public_key();
+        assert!(matches!(
+            public_key
+                .verify_cose_sign1::<String, String>(&[0xff])
+                .unwrap_err(),
+            CoseError::Cbor
+        ));
+        assert!(matches!(
+            public_key
+                .verify_cose_sign1::<String, String>(&to_cbor(&[1, 2, 3]).unwrap())
+                .unwrap_err(),
+            CoseError::Format
+        ));
+
+        // ES256 in protected headers
+        let protected = to_cbor(&Value::Map(vec![(ALG.into(), (-7).into())])).unwrap();
+        let cose = Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(vec![]),
+            Value::Bytes(vec![]),
+            Value::Bytes(vec![0; 64]),
+        ]);
+        assert!(matches!(
+            public_key
+                .verify_cose_sign1::<String, String>(&to_cbor(&cose).unwrap())
+                .unwrap_err(),
+            CoseError::Algorithm
+        ));
+    }
+}
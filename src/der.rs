@@ -0,0 +1,136 @@
+//! Minimal DER encoding and decoding, for the few ASN.1 structures of standard key formats
+
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const OID: u8 = 0x06;
+pub const SEQUENCE: u8 = 0x30;
+#[cfg(feature = "sign")]
+pub const SET: u8 = 0x31;
+
+/// Context-specific tag `[n]`, constructed or not
+pub const fn context(n: u8, constructed: bool) -> u8 {
+    0x80 | if constructed { 0x20 } else { 0 } | n
+}
+
+/// Encoded object identifier of Ed25519 keys, 1.3.101.112
+pub const ED25519_OID: &[u8] = &[0x2b, 0x65, 0x70];
+
+/// Encode a value with its tag and length
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    match content.len() {
+        length @ 0..0x80 => encoded.push(length as u8),
+        length => {
+            let bytes = length.to_be_bytes();
+            let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+            encoded.push(0x80 | (bytes.len() - skip) as u8);
+            encoded.extend_from_slice(&bytes[skip..]);
+        }
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+/// Encode values one after the other in a sequence
+pub fn sequence(values: &[&[u8]]) -> Vec<u8> {
+    encode(SEQUENCE, &values.concat())
+}
+
+/// Encode a bit string without unused bits
+pub fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    encode(BIT_STRING, &[&[0], bytes].concat())
+}
+
+/// Reader of DER values, one after the other
+pub struct Reader<'a>(pub &'a [u8]);
+
+impl<'a> Reader<'a> {
+    /// Tag of the next value
+    pub fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    /// Whether all values were read
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Read the next value, its tag must be `tag`
+    pub fn read(&mut self, tag: u8) -> Option<&'a [u8]> {
+        let (&actual, rest) = self.0.split_first()?;
+        if actual != tag {
+            return None;
+        }
+
+        let (&first, rest) = rest.split_first()?;
+        let (length, rest) = match first {
+            length @ 0..=0x7f => (length as usize, rest),
+            0x81..=0x84 => {
+                let count = usize::from(first & 0x7f);
+                if rest.len() < count {
+                    return None;
+                }
+                let (bytes, rest) = rest.split_at(count);
+                let length = bytes
+                    .iter()
+                    .fold(0usize, |length, byte| (length << 8) | usize::from(*byte));
+                (length, rest)
+            }
+            _ => return None,
+        };
+
+        if rest.len() < length {
+            return None;
+        }
+        let (content, rest) = rest.split_at(length);
+        self.0 = rest;
+        Some(content)
+    }
+
+    /// Read a bit string without unused bits
+    pub fn read_bit_string(&mut self) -> Option<&'a [u8]> {
+        match self.read(BIT_STRING)?.split_first()? {
+            (0, bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Read the `AlgorithmIdentifier` sequence of Ed25519, without parameters
+    pub fn read_ed25519_algorithm(&mut self) -> Option<()> {
+        let mut algorithm = Reader(self.read(SEQUENCE)?);
+        (algorithm.read(OID)? == ED25519_OID && algorithm.is_empty()).then_some(())
+    }
+}
+
+/// `AlgorithmIdentifier` sequence of Ed25519
+pub fn ed25519_algorithm() -> Vec<u8> {
+    sequence(&[&encode(OID, ED25519_OID)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length() {
+        assert_eq!(encode(OCTET_STRING, &[7; 3]), [0x04, 0x03, 7, 7, 7]);
+        assert_eq!(&encode(OCTET_STRING, &[7; 200])[..3], [0x04, 0x81, 200]);
+        assert_eq!(
+            &encode(OCTET_STRING, &[7; 300])[..4],
+            [0x04, 0x82, 0x01, 0x2c]
+        );
+
+        let long = encode(OCTET_STRING, &[7; 300]);
+        let mut reader = Reader(&long);
+        assert_eq!(reader.read(OCTET_STRING).unwrap().len(), 300);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(Reader(&[0x04, 0x05, 1, 2]).read(OCTET_STRING).is_none());
+        assert!(Reader(&[0x04, 0x82, 1]).read(OCTET_STRING).is_none());
+        assert!(Reader(&[0x02, 0x01, 1]).read(OCTET_STRING).is_none());
+    }
+}
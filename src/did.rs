@@ -0,0 +1,183 @@
+//! Identify public keys with [`did:key`](https://w3c-ccg.github.io/did-key-spec/) decentralized
+//! identifiers
+//!
+//! The identifier is the Ed25519 key prefixed by its multicodec (`ed25519-pub`, `0xed01`), encoded
+//! in multibase base58btc (`z`): identifiers of Ed25519 keys always start with `did:key:z6Mk`.
+//!
+//! A DID has no room for the key number, keys parsed from one get a key number derived from the
+//! key. To verify a [`Signature`] with a DID, use [`Signature::verify_did()`], which does not need
+//! key numbers to match.
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//! let builder = sigserlic::SignatureBuilder::<_, ()>::new(String::from("toto"));
+//! let signature = signing_key.sign(builder).unwrap();
+//!
+//! let did = sigserlic::PublicKey::from(signing_key).to_did_key();
+//! assert!(did.starts_with("did:key:z6Mk"));
+//! let message = signature.verify_did(&did).unwrap();
+//! assert_eq!(message.data(), "toto");
+//! ```
+
+use crate::error::SignatureError;
+use crate::{Message, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+/// Method of DIDs made of a key
+const DID_KEY_PREFIX: &str = "did:key:";
+
+/// Multibase prefix of base58btc
+pub(crate) const BASE58BTC: char = 'z';
+
+/// Multicodec of Ed25519 public keys, as an unsigned varint
+const ED25519_PUB: [u8; 2] = [0xed, 0x01];
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors when parsing a DID or verifying a signature with one
+pub enum DidError {
+    #[snafu(display("not a `did:key` identifier"))]
+    /// The DID uses another method than `key`
+    Method,
+    #[snafu(display("multibase encoding is not base58btc"))]
+    /// The key is not encoded in base58btc, prefixed by `z`
+    Multibase,
+    #[snafu(display("decoding base58"))]
+    /// The key is not valid base58
+    Base58 {
+        /// Original error
+        source: bs58::decode::Error,
+    },
+    #[snafu(display("not an Ed25519 public key"))]
+    /// The multicodec is not `ed25519-pub`, or the key is not 32 bytes
+    Multicodec,
+    #[snafu(display("verify signature"))]
+    /// Signature could not be decoded, or is not authentic
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+}
+
+/// Raw Ed25519 key of a `did:key`, a DID URL fragment is ignored
+pub(crate) fn parse_did_key(did: &str) -> Result<[u8; 32], DidError> {
+    let did = did.split_once('#').map_or(did, |(did, _)| did);
+    let multibase = did.strip_prefix(DID_KEY_PREFIX).ok_or(DidError::Method)?;
+    parse_multibase(multibase)
+}
+
+/// Raw Ed25519 key of a multibase multicodec key
+fn parse_multibase(multibase: &str) -> Result<[u8; 32], DidError> {
+    let base58 = multibase
+        .strip_prefix(BASE58BTC)
+        .ok_or(DidError::Multibase)?;
+    let bytes = bs58::decode(base58).into_vec().context(Base58Snafu)?;
+    bytes
+        .strip_prefix(&ED25519_PUB)
+        .and_then(|key| <[u8; 32]>::try_from(key).ok())
+        .ok_or(DidError::Multicodec)
+}
+
+impl<C> PublicKey<C> {
+    /// Encode the key in multibase with its multicodec, `z6Mk...`, as used by `publicKeyMultibase`
+    pub fn to_multibase(&self) -> String {
+        let bytes = [ED25519_PUB.as_slice(), &self.key_bytes()].concat();
+        format!("{BASE58BTC}{}", bs58::encode(bytes).into_string())
+    }
+
+    /// Identify the key with a `did:key`, `did:key:z6Mk...`
+    pub fn to_did_key(&self) -> String {
+        format!("{DID_KEY_PREFIX}{}", self.to_multibase())
+    }
+
+    /// Parse a key encoded in multibase with its multicodec, with default metadata
+    pub fn from_multibase(multibase: &str) -> Result<Self, DidError> {
+        Ok(Self::from_key_bytes(&parse_multibase(multibase)?, None))
+    }
+
+    /// Parse the key of a `did:key`, with default metadata
+    ///
+    /// The key number is derived from the key, see the [module documentation](self).
+    pub fn from_did_key(did: &str) -> Result<Self, DidError> {
+        Ok(Self::from_key_bytes(&parse_did_key(did)?, None))
+    }
+}
+
+impl<'de, T: Serialize + Deserialize<'de>, C> Signature<T, C> {
+    /// Decode signature and signed data, verify authenticity of signature with the key of a
+    /// `did:key`
+    ///
+    /// The DID identifies the key alone, the key number of the signature is not checked.
+    pub fn verify_did(self, did: &str) -> Result<Message<T>, DidError> {
+        let key = parse_did_key(did)?;
+        let signer = self.signature().context(SignatureSnafu)?.signer_keynum();
+        let keynum = signer.as_ref().try_into().expect("8 bytes");
+        let public_key = PublicKey::<()>::from_key_bytes(&key, Some(keynum));
+
+        self.verify(&public_key).context(SignatureSnafu)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::{COMMENTED_SIGNING_KEY_JSON, other_public_key};
+    use crate::{KeyMetadata, SignatureBuilder, SigningKey};
+
+    #[test]
+    fn spec_vector() {
+        // did:key test vectors, ed25519-x25519.json
+        let did = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+        let public_key = PublicKey::<()>::from_did_key(did).unwrap();
+        assert_eq!(
+            bs58::encode(public_key.key_bytes()).into_string(),
+            "4zvwRjXUKGfvwnParsHAS3HuSVzV5cA4McphgmoCtajS"
+        );
+        assert_eq!(public_key.to_did_key(), did);
+
+        // DID URL of the verification method
+        let url = format!("{did}#z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp");
+        let from_url = PublicKey::<()>::from_did_key(&url).unwrap();
+        assert_eq!(from_url.keynum(), public_key.keynum());
+    }
+
+    #[test]
+    fn verify() {
+        let signing_key: SigningKey<String> =
+            serde_json::from_str(COMMENTED_SIGNING_KEY_JSON).unwrap();
+        let builder = SignatureBuilder::<_, ()>::new(String::from("toto"));
+        let signature = signing_key.sign(builder).unwrap();
+        let json = serde_json::to_string(&signature).unwrap();
+
+        let did = PublicKey::from(signing_key).to_did_key();
+        let message = signature.verify_did(&did).unwrap();
+        assert_eq!(message.data(), "toto");
+
+        let other = other_public_key();
+        let signature: Signature<String, ()> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            signature.verify_did(&other.to_did_key()).unwrap_err(),
+            DidError::Signature { .. }
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            PublicKey::<()>::from_did_key("did:web:example.com").unwrap_err(),
+            DidError::Method
+        );
+        assert_eq!(
+            PublicKey::<()>::from_did_key("did:key:uAAAA").unwrap_err(),
+            DidError::Multibase
+        );
+        // secp256k1 key
+        assert_eq!(
+            PublicKey::<()>::from_did_key(
+                "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme"
+            )
+            .unwrap_err(),
+            DidError::Multicodec
+        );
+    }
+}
@@ -0,0 +1,486 @@
+//! Sign supply-chain attestations with DSSE envelopes and in-toto statements
+//!
+//! A [DSSE](https://github.com/secure-systems-lab/dsse) [`Envelope`] holds a payload with its
+//! type, and any number of signatures over both. [`Statement`] is the typed
+//! [in-toto Statement v1](https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md)
+//! payload, describing artifacts with a predicate.
+//!
+//! ```
+//! use sigserlic::dsse::{ResourceDescriptor, Statement};
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Review {
+//!     approved: bool,
+//! }
+//!
+//! let statement = Statement::new("https://example.com/review/v1", Review { approved: true })
+//!     .subject(ResourceDescriptor::sha256("toto.tar.gz", b"content of toto"));
+//! let envelope = statement.to_envelope().unwrap().sign(&signing_key);
+//!
+//! let json = serde_json::to_string(&envelope).unwrap();
+//!
+//! let envelope: sigserlic::dsse::Envelope = serde_json::from_str(&json).unwrap();
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//! let statement: Statement<Review> = envelope.verify_statement(&public_key).unwrap();
+//! assert!(statement.predicate().approved);
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::key::key_id;
+use crate::{KeyMetadata, PublicKey};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Payload type of in-toto statements
+pub const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// Type of in-toto statements, version 1
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Signature of an [`Envelope`]
+pub struct EnvelopeSignature {
+    /// Key number of the signing key, encoded in base64url
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keyid: String,
+    /// Raw Ed25519 signature
+    #[serde(with = "crate::base64_serde::vec")]
+    pub sig: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// DSSE envelope: a typed payload and its signatures
+pub struct Envelope {
+    #[serde(with = "crate::base64_serde::vec")]
+    payload: Vec<u8>,
+    payload_type: String,
+    signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when verifying a DSSE envelope
+pub enum DsseError {
+    #[snafu(display("no signature of the envelope is valid for the key"))]
+    /// No signature was made by the key
+    NoSignature,
+    #[snafu(display("threshold of zero keys"))]
+    /// A threshold of zero would accept envelopes without any valid signature
+    ZeroThreshold,
+    #[snafu(display("{verified} keys signed the envelope, {required} are required"))]
+    /// Not enough keys of the set signed the envelope
+    Threshold {
+        /// Number of keys with a valid signature
+        verified: usize,
+        /// Number of keys required
+        required: usize,
+    },
+    #[snafu(display("unexpected payload type `{payload_type}`"))]
+    /// Payload is not of the expected type
+    PayloadType {
+        /// Type of the payload
+        payload_type: String,
+    },
+    #[snafu(display("unexpected statement type `{statement_type}`"))]
+    /// Payload is not an in-toto statement v1
+    StatementType {
+        /// Type of the statement
+        statement_type: String,
+    },
+    #[snafu(display("payload in json"))]
+    /// Failed to encode or decode the payload with json
+    Json {
+        /// Original error
+        source: serde_json::Error,
+    },
+}
+
+/// Pre-authentication encoding, data which is actually signed
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut encoded = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    )
+    .into_bytes();
+    encoded.extend_from_slice(payload);
+    encoded
+}
+
+impl Envelope {
+    /// Envelope without signatures
+    pub fn new(payload_type: impl Into<String>, payload: Vec<u8>) -> Self {
+        Self {
+            payload,
+            payload_type: payload_type.into(),
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Payload, **not verified**
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Type of the payload, **not verified**
+    pub fn payload_type(&self) -> &str {
+        &self.payload_type
+    }
+
+    /// Signatures of the envelope
+    pub fn signatures(&self) -> &[EnvelopeSignature] {
+        &self.signatures
+    }
+
+    #[cfg(feature = "sign")]
+    /// Add a signature of payload and its type, envelopes can be signed by multiple keys
+    pub fn sign<C>(mut self, signing_key: &SigningKey<C>) -> Self {
+        let sig = signing_key.sign_raw(&pae(&self.payload_type, &self.payload));
+        self.signatures.push(EnvelopeSignature {
+            keyid: key_id(signing_key.keynum()),
+            sig: sig.to_vec(),
+        });
+        self
+    }
+
+    /// Whether one of the signatures was made by the key
+    fn is_signed_by<C>(&self, public_key: &PublicKey<C>) -> bool {
+        let keyid = key_id(public_key.keynum());
+        let message = pae(&self.payload_type, &self.payload);
+
+        self.signatures
+            .iter()
+            // Signatures without identifier can be from any key
+            .filter(|signature| signature.keyid.is_empty() || signature.keyid == keyid)
+            .any(|signature| public_key.verify_raw(&message, &signature.sig).is_ok())
+    }
+
+    /// Verify that the envelope is signed by `public_key`, and get the payload
+    pub fn verify<C>(&self, public_key: &PublicKey<C>) -> Result<&[u8], DsseError> {
+        match self.is_signed_by(public_key) {
+            true => Ok(&self.payload),
+            false => Err(DsseError::NoSignature),
+        }
+    }
+
+    /// Verify that the envelope is signed by at least `threshold` distinct keys of
+    /// `public_keys`, and get the payload
+    ///
+    /// Keys are told apart by their Ed25519 key, not by their key number: the same key imported
+    /// twice with different key numbers counts once.
+    pub fn verify_threshold<C>(
+        &self,
+        public_keys: &[PublicKey<C>],
+        threshold: usize,
+    ) -> Result<&[u8], DsseError> {
+        if threshold == 0 {
+            return Err(DsseError::ZeroThreshold);
+        }
+
+        let verified = public_keys
+            .iter()
+            .filter(|public_key| self.is_signed_by(public_key))
+            .map(|public_key| public_key.key_bytes())
+            .collect::<BTreeSet<_>>()
+            .len();
+
+        match verified >= threshold {
+            true => Ok(&self.payload),
+            false => Err(DsseError::Threshold {
+                verified,
+                required: threshold,
+            }),
+        }
+    }
+
+    /// Verify that the envelope is signed by `public_key`, and get the in-toto statement it holds
+    pub fn verify_statement<P: DeserializeOwned, C>(
+        &self,
+        public_key: &PublicKey<C>,
+    ) -> Result<Statement<P>, DsseError> {
+        let payload = self.verify(public_key)?;
+        self.statement(payload)
+    }
+
+    /// Verify that the envelope is signed by at least `threshold` distinct keys of `public_keys`,
+    /// like [`Envelope::verify_threshold()`], and get the in-toto statement it holds
+    pub fn verify_statement_threshold<P: DeserializeOwned, C>(
+        &self,
+        public_keys: &[PublicKey<C>],
+        threshold: usize,
+    ) -> Result<Statement<P>, DsseError> {
+        let payload = self.verify_threshold(public_keys, threshold)?;
+        self.statement(payload)
+    }
+
+    /// In-toto statement of a verified `payload`
+    fn statement<P: DeserializeOwned>(&self, payload: &[u8]) -> Result<Statement<P>, DsseError> {
+        if self.payload_type != IN_TOTO_PAYLOAD_TYPE {
+            return Err(DsseError::PayloadType {
+                payload_type: self.payload_type.clone(),
+            });
+        }
+
+        let statement: Statement<P> = serde_json::from_slice(payload).context(JsonSnafu)?;
+        if statement.statement_type != STATEMENT_TYPE {
+            return Err(DsseError::StatementType {
+                statement_type: statement.statement_type,
+            });
+        }
+        Ok(statement)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Artifact described by a [`Statement`]
+pub struct ResourceDescriptor {
+    name: String,
+    /// Algorithm and digest encoded in hexadecimal
+    digest: BTreeMap<String, String>,
+}
+
+impl ResourceDescriptor {
+    /// Artifact identified by its SHA-256 digest
+    pub fn sha256(name: impl Into<String>, content: &[u8]) -> Self {
+        let digest = Sha256::digest(content)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        Self {
+            name: name.into(),
+            digest: BTreeMap::from([("sha256".into(), digest)]),
+        }
+    }
+
+    /// Name of the artifact
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Digests of the artifact, by algorithm
+    pub fn digest(&self) -> &BTreeMap<String, String> {
+        &self.digest
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// in-toto Statement v1: a predicate about artifacts
+pub struct Statement<P> {
+    #[serde(rename = "_type")]
+    statement_type: String,
+    subject: Vec<ResourceDescriptor>,
+    predicate_type: String,
+    predicate: P,
+}
+
+impl<P> Statement<P> {
+    /// Statement without subjects, where `predicate_type` is an URI describing the predicate
+    pub fn new(predicate_type: impl Into<String>, predicate: P) -> Self {
+        Self {
+            statement_type: STATEMENT_TYPE.into(),
+            subject: Vec::new(),
+            predicate_type: predicate_type.into(),
+            predicate,
+        }
+    }
+
+    /// Add an artifact the predicate applies to
+    pub fn subject(mut self, subject: ResourceDescriptor) -> Self {
+        self.subject.push(subject);
+        self
+    }
+
+    /// Artifacts the predicate applies to
+    pub fn subjects(&self) -> &[ResourceDescriptor] {
+        &self.subject
+    }
+
+    /// URI describing the predicate
+    pub fn predicate_type(&self) -> &str {
+        &self.predicate_type
+    }
+
+    /// Content of the statement
+    pub fn predicate(&self) -> &P {
+        &self.predicate
+    }
+}
+
+impl<P: Serialize> Statement<P> {
+    /// Envelope holding the statement, waiting to be signed
+    pub fn to_envelope(&self) -> Result<Envelope, DsseError> {
+        let payload = serde_json::to_vec(self).context(JsonSnafu)?;
+        Ok(Envelope::new(IN_TOTO_PAYLOAD_TYPE, payload))
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::{other_key_pair, public_key, signing_key};
+
+    #[test]
+    fn pae_of_specification() {
+        assert_eq!(
+            pae("http://example.com/HelloWorld", b"hello world"),
+            b"DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+        );
+    }
+
+    #[test]
+    fn envelope_json() {
+        let envelope = Envelope::new("text/plain", b"toto".to_vec()).sign(&signing_key());
+        let json: serde_json::Value = serde_json::to_value(&envelope).unwrap();
+
+        assert_eq!(json["payload"], "dG90bw==");
+        assert_eq!(json["payloadType"], "text/plain");
+        assert_eq!(json["signatures"][0]["keyid"], "l6436od-WIw");
+
+        let imported: Envelope = serde_json::from_value(json).unwrap();
+        assert_eq!(imported.verify(&public_key()).unwrap(), b"toto");
+    }
+
+    #[test]
+    fn altered_payload_type() {
+        let mut envelope = Envelope::new("text/plain", b"toto".to_vec()).sign(&signing_key());
+        envelope.payload_type = "text/html".into();
+
+        assert!(matches!(
+            envelope.verify(&public_key()).unwrap_err(),
+            DsseError::NoSignature
+        ));
+    }
+
+    #[test]
+    fn multiple_signatures() {
+        let (other_signing_key, other_public_key) = other_key_pair::<()>();
+        let envelope = Envelope::new("text/plain", b"toto".to_vec())
+            .sign(&signing_key())
+            .sign(&other_signing_key);
+        assert_eq!(envelope.signatures().len(), 2);
+        assert!(envelope.verify(&public_key()).is_ok());
+        assert!(envelope.verify(&other_public_key).is_ok());
+
+        let keys = [public_key(), other_public_key];
+        assert!(envelope.verify_threshold(&keys, 2).is_ok());
+
+        let partial = Envelope::new("text/plain", b"toto".to_vec()).sign(&signing_key());
+        assert!(matches!(
+            partial.verify_threshold(&keys, 2).unwrap_err(),
+            DsseError::Threshold {
+                verified: 1,
+                required: 2
+            }
+        ));
+
+        // The same key listed twice counts once
+        let same = [public_key(), public_key()];
+        assert!(partial.verify_threshold(&same, 2).is_err());
+    }
+
+    #[test]
+    fn same_key_under_two_keynums() {
+        let public_key = public_key();
+        let imported = PublicKey::<()>::from_key_bytes(&public_key.key_bytes(), Some([1; 8]));
+        assert_ne!(public_key.keynum(), imported.keynum());
+
+        // Signer adds its signature again, under the identifier of the other key number
+        let mut envelope = Envelope::new("text/plain", b"toto".to_vec()).sign(&signing_key());
+        envelope.signatures.push(EnvelopeSignature {
+            keyid: key_id(imported.keynum()),
+            sig: envelope.signatures[0].sig.clone(),
+        });
+
+        assert!(matches!(
+            envelope
+                .verify_threshold(&[public_key, imported], 2)
+                .unwrap_err(),
+            DsseError::Threshold {
+                verified: 1,
+                required: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn signature_without_keyid() {
+        let mut envelope = Envelope::new("text/plain", b"toto".to_vec()).sign(&signing_key());
+        envelope.signatures[0].keyid.clear();
+        assert!(envelope.verify(&public_key()).is_ok());
+
+        // Tried against every key, it still only counts for the key which made it
+        let keys = [public_key(), other_key_pair::<()>().1];
+        assert!(envelope.verify_threshold(&keys, 1).is_ok());
+        assert!(matches!(
+            envelope.verify_threshold(&keys, 2).unwrap_err(),
+            DsseError::Threshold {
+                verified: 1,
+                required: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn zero_threshold() {
+        let envelope = Envelope::new("text/plain", b"toto".to_vec());
+        assert!(matches!(
+            envelope.verify_threshold(&[public_key()], 0).unwrap_err(),
+            DsseError::ZeroThreshold
+        ));
+    }
+
+    #[test]
+    fn statement() {
+        let statement = Statement::new("https://example.com/review/v1", "approved".to_string())
+            .subject(ResourceDescriptor::sha256("toto.txt", b"toto"));
+        let envelope = statement.to_envelope().unwrap().sign(&signing_key());
+
+        let payload: serde_json::Value = serde_json::from_slice(envelope.payload()).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "_type": "https://in-toto.io/Statement/v1",
+                "subject": [{
+                    "name": "toto.txt",
+                    "digest": {"sha256": "31f7a65e315586ac198bd798b6629ce4903d0899476d5741a9f32e2e521b6a66"},
+                }],
+                "predicateType": "https://example.com/review/v1",
+                "predicate": "approved",
+            })
+        );
+
+        let verified: Statement<String> = envelope.verify_statement(&public_key()).unwrap();
+        assert_eq!(verified, statement);
+
+        let keys = [public_key(), other_key_pair::<()>().1];
+        let verified: Statement<String> = envelope.verify_statement_threshold(&keys, 1).unwrap();
+        assert_eq!(verified, statement);
+        assert!(matches!(
+            envelope
+                .verify_statement_threshold::<String, _>(&keys, 2)
+                .unwrap_err(),
+            DsseError::Threshold {
+                verified: 1,
+                required: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn not_a_statement() {
+        let envelope = Envelope::new("text/plain", b"toto".to_vec()).sign(&signing_key());
+        assert!(matches!(
+            envelope
+                .verify_statement::<String, _>(&public_key())
+                .unwrap_err(),
+            DsseError::PayloadType { payload_type } if payload_type == "text/plain"
+        ));
+    }
+}
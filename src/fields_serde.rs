@@ -0,0 +1,54 @@
+//! Structs whose fields are read differently when given by name or by position
+//!
+//! Binary formats giving fields by name (CBOR, MessagePack maps) describe their values too: fields
+//! of keys and signatures may then be the strings written up to 0.2.0, as well as raw bytes and
+//! integers. Formats giving fields by position (bincode, postcard) only know the latter.
+
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+/// Deserialize struct `name` as `Named` when the format gives its fields by name, as `Positional`
+/// otherwise
+pub(crate) fn deserialize<'de, D, Named, Positional, T>(
+    deserializer: D,
+    name: &'static str,
+    fields: &'static [&'static str],
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    Named: Deserialize<'de> + Into<T>,
+    Positional: Deserialize<'de> + Into<T>,
+{
+    let visitor = FieldsVisitor {
+        name,
+        fields: PhantomData::<(Named, Positional, T)>,
+    };
+    deserializer.deserialize_struct(name, fields, visitor)
+}
+
+struct FieldsVisitor<F> {
+    name: &'static str,
+    fields: PhantomData<F>,
+}
+
+impl<'de, Named, Positional, T> Visitor<'de> for FieldsVisitor<(Named, Positional, T)>
+where
+    Named: Deserialize<'de> + Into<T>,
+    Positional: Deserialize<'de> + Into<T>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "struct {}", self.name)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        Named::deserialize(MapAccessDeserializer::new(map)).map(Into::into)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        Positional::deserialize(SeqAccessDeserializer::new(seq)).map(Into::into)
+    }
+}
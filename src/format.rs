@@ -0,0 +1,371 @@
+//! Loading of keys and signatures from bytes in any supported serde format
+//!
+//! [`SigningKey`](crate::SigningKey), [`PublicKey`](crate::PublicKey) and
+//! [`Signature`](crate::Signature) round-trip through JSON, CBOR, TOML, YAML, MessagePack and
+//! postcard. [`load()`] recognizes the format of the bytes with [`detect()`] before
+//! deserializing them:
+//!
+//! - JSON and CBOR are always supported
+//! - TOML, YAML and MessagePack need the `toml`, `yaml` and `msgpack` features
+//! - postcard is not self-describing and can't be recognized, use `postcard::from_bytes()`
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<String>::generate().with_comment("toto".into());
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//!
+//! let mut cbor = Vec::new();
+//! ciborium::into_writer(&public_key, &mut cbor).unwrap();
+//! assert_eq!(sigserlic::format::detect(&cbor), Some(sigserlic::format::Format::Cbor));
+//!
+//! let imported: sigserlic::PublicKey<String> = sigserlic::format::load(&cbor).unwrap();
+//! ```
+
+use serde::de::DeserializeOwned;
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Serde format recognized by [`detect()`]
+pub enum Format {
+    /// JSON, starting with `{`
+    Json,
+    /// CBOR map, optionally with the self-described CBOR tag
+    Cbor,
+    /// TOML document
+    Toml,
+    /// YAML document
+    Yaml,
+    /// MessagePack map (`rmp_serde::to_vec_named()`) or array (`rmp_serde::to_vec()`)
+    MessagePack,
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::Cbor => "cbor",
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+            Self::MessagePack => "messagepack",
+        })
+    }
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when loading a value with [`load()`]
+pub enum FormatError {
+    #[snafu(display("unrecognized format"))]
+    /// Bytes are not in any format recognized by [`detect()`]
+    Unknown,
+    #[snafu(display("support of {format} is not enabled"))]
+    /// Format was recognized, but the cargo feature supporting it is not enabled
+    Disabled {
+        /// Recognized format
+        format: Format,
+    },
+    #[snafu(display("decoding json"))]
+    /// Failed to deserialize JSON
+    Json {
+        /// Original error
+        source: serde_json::Error,
+    },
+    #[snafu(display("decoding cbor"))]
+    /// Failed to deserialize CBOR
+    Cbor {
+        /// Original error
+        source: ciborium::de::Error<std::io::Error>,
+    },
+    #[cfg(feature = "toml")]
+    #[snafu(display("decoding toml"))]
+    /// Failed to deserialize TOML
+    Toml {
+        /// Original error
+        source: toml::de::Error,
+    },
+    #[cfg(feature = "yaml")]
+    #[snafu(display("decoding yaml"))]
+    /// Failed to deserialize YAML
+    Yaml {
+        /// Original error
+        source: serde_yaml::Error,
+    },
+    #[cfg(feature = "msgpack")]
+    #[snafu(display("decoding messagepack"))]
+    /// Failed to deserialize MessagePack
+    MessagePack {
+        /// Original error
+        source: rmp_serde::decode::Error,
+    },
+}
+
+/// Self-described CBOR tag (RFC 8949, section 3.4.6)
+const CBOR_SELF_DESCRIBED: [u8; 3] = [0xd9, 0xd9, 0xf7];
+
+/// Recognize the format of serialized bytes of a struct
+///
+/// Binary formats are recognized by their first byte, a map in CBOR or a map or an array in
+/// MessagePack. Text starting with `{` is JSON, otherwise the first line telling a key from a
+/// value with `=` is TOML, and with `:` is YAML.
+pub fn detect(bytes: &[u8]) -> Option<Format> {
+    if bytes.starts_with(&CBOR_SELF_DESCRIBED) {
+        return Some(Format::Cbor);
+    }
+    match bytes.first()? {
+        0xa0..=0xbf => return Some(Format::Cbor),
+        0x80..=0x9f | 0xdc..=0xdf => return Some(Format::MessagePack),
+        _ => (),
+    }
+
+    let text = std::str::from_utf8(bytes).ok()?.trim_start();
+    if text.starts_with('{') {
+        return Some(Format::Json);
+    }
+    if text.starts_with("---") {
+        return Some(Format::Yaml);
+    }
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .find_map(|line| {
+            if line.starts_with('[') {
+                return Some(Format::Toml);
+            }
+            match line.find(['=', ':'])? {
+                index if line.as_bytes()[index] == b'=' => Some(Format::Toml),
+                _ => Some(Format::Yaml),
+            }
+        })
+}
+
+/// Deserialize a value from bytes in the format recognized by [`detect()`]
+pub fn load<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, FormatError> {
+    match detect(bytes).ok_or(FormatError::Unknown)? {
+        Format::Json => serde_json::from_slice(bytes).context(JsonSnafu),
+        Format::Cbor => ciborium::from_reader(bytes).context(CborSnafu),
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            // Detection made sure bytes are valid utf-8
+            let text = std::str::from_utf8(bytes).expect("toml is utf-8");
+            toml::from_str(text).context(TomlSnafu)
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => serde_yaml::from_slice(bytes).context(YamlSnafu),
+        #[cfg(feature = "msgpack")]
+        Format::MessagePack => rmp_serde::from_slice(bytes).context(MessagePackSnafu),
+        #[allow(unreachable_patterns)]
+        format => Err(FormatError::Disabled { format }),
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::other_key_pair;
+    use crate::{KeyMetadata, Nonce, PublicKey, Signature, SignatureBuilder, SigningKey};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct License {
+        name: String,
+        seats: u32,
+        features: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Comment {
+        owner: String,
+    }
+
+    type Key = SigningKey<Comment>;
+    type Sig = Signature<License, Comment>;
+
+    /// Keys and a signature with every optional field set, or none of them
+    fn fixtures(complete: bool) -> (Key, PublicKey<Comment>, Sig) {
+        let (mut signing_key, mut public_key): (Key, _) = other_key_pair();
+        let mut builder = SignatureBuilder::new(License {
+            name: "toto".into(),
+            seats: 5,
+            features: vec!["gateau".into(), "chocolat".into()],
+        });
+        if complete {
+            for metadata in [&mut signing_key.metadata, &mut public_key.metadata] {
+                let owner = "authority".into();
+                metadata.comment = Some(Comment { owner });
+                metadata.expired_at = Some(crate::timestamp::parse_timestamp(1900000000).unwrap());
+            }
+            let owner = "consumer".into();
+            builder = builder
+                .expiration(1800000000)
+                .unwrap()
+                .nonce(Nonce::from([7; Nonce::LEN]))
+                .comment(Comment { owner });
+        }
+        let signature = builder.sign(&signing_key).unwrap();
+
+        (signing_key, public_key, signature)
+    }
+
+    fn assert_same_key<C: std::fmt::Debug + PartialEq>(
+        a: &impl KeyMetadata<C>,
+        b: &impl KeyMetadata<C>,
+    ) {
+        assert_eq!(a.keynum(), b.keynum());
+        assert_eq!(a.created_at(), b.created_at());
+        assert_eq!(a.expired_at(), b.expired_at());
+        assert_eq!(a.comment(), b.comment());
+    }
+
+    /// Round-trip keys and signature through a format, and verify the signature afterwards
+    macro_rules! conformance {
+        ($name:ident, $encode:expr, $decode:expr) => {
+            #[test]
+            fn $name() {
+                for complete in [false, true] {
+                    let (signing_key, public_key, signature) = fixtures(complete);
+
+                    let decoded: Key = $decode(&$encode(&signing_key).unwrap()).unwrap();
+                    assert_same_key(&decoded, &signing_key);
+                    let from_signing_key = PublicKey::from(decoded);
+
+                    let decoded: PublicKey<Comment> =
+                        $decode(&$encode(&public_key).unwrap()).unwrap();
+                    assert_same_key(&decoded, &public_key);
+
+                    let decoded: Sig = $decode(&$encode(&signature).unwrap()).unwrap();
+                    assert_eq!(decoded.comment(), signature.comment());
+                    assert_eq!(
+                        decoded.verify(&from_signing_key).unwrap(),
+                        signature.verify(&public_key).unwrap()
+                    );
+                }
+            }
+        };
+    }
+
+    fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn from_cbor<T: serde::de::DeserializeOwned>(
+        bytes: &[u8],
+    ) -> Result<T, ciborium::de::Error<std::io::Error>> {
+        ciborium::from_reader(bytes)
+    }
+
+    fn from_toml<T: serde::de::DeserializeOwned>(bytes: &str) -> Result<T, toml::de::Error> {
+        toml::from_str(bytes)
+    }
+
+    conformance!(json, serde_json::to_vec, serde_json::from_slice);
+    conformance!(
+        json_pretty,
+        serde_json::to_string_pretty,
+        serde_json::from_str
+    );
+    conformance!(cbor, to_cbor, from_cbor);
+    conformance!(toml, toml::to_string, from_toml);
+    conformance!(yaml, serde_yaml::to_string, serde_yaml::from_str);
+    conformance!(msgpack, rmp_serde::to_vec, rmp_serde::from_slice);
+    conformance!(
+        msgpack_named,
+        rmp_serde::to_vec_named,
+        rmp_serde::from_slice
+    );
+    conformance!(postcard, postcard::to_stdvec, postcard::from_bytes);
+
+    #[test]
+    fn detect_formats() {
+        let (signing_key, _, signature) = fixtures(true);
+
+        let json = serde_json::to_vec(&signing_key).unwrap();
+        assert_eq!(detect(&json), Some(Format::Json));
+        let json = serde_json::to_vec_pretty(&signature).unwrap();
+        assert_eq!(detect(&json), Some(Format::Json));
+
+        let cbor = to_cbor(&signing_key).unwrap();
+        assert_eq!(detect(&cbor), Some(Format::Cbor));
+        let mut tagged = CBOR_SELF_DESCRIBED.to_vec();
+        tagged.extend_from_slice(&cbor);
+        assert_eq!(detect(&tagged), Some(Format::Cbor));
+
+        for value in [toml::to_string(&signing_key), toml::to_string(&signature)] {
+            assert_eq!(detect(value.unwrap().as_bytes()), Some(Format::Toml));
+        }
+        for value in [
+            serde_yaml::to_string(&signing_key),
+            serde_yaml::to_string(&signature),
+        ] {
+            assert_eq!(detect(value.unwrap().as_bytes()), Some(Format::Yaml));
+        }
+
+        let msgpack = rmp_serde::to_vec(&signing_key).unwrap();
+        assert_eq!(detect(&msgpack), Some(Format::MessagePack));
+        let msgpack = rmp_serde::to_vec_named(&signature).unwrap();
+        assert_eq!(detect(&msgpack), Some(Format::MessagePack));
+
+        assert_eq!(detect(b""), None);
+        assert_eq!(detect(b"toto mange du gateau"), None);
+        assert_eq!(detect(&[0xff, 0xfe]), None);
+    }
+
+    #[test]
+    fn load_json_and_cbor() {
+        let (_, public_key, signature) = fixtures(true);
+
+        let json = serde_json::to_vec(&signature).unwrap();
+        let loaded: Sig = load(&json).unwrap();
+        assert!(loaded.verify(&public_key).is_ok());
+
+        let cbor = to_cbor(&signature).unwrap();
+        let loaded: Sig = load(&cbor).unwrap();
+        assert!(loaded.verify(&public_key).is_ok());
+
+        assert!(matches!(load::<Sig>(b"toto"), Err(FormatError::Unknown)));
+        assert!(matches!(load::<Sig>(b"{}"), Err(FormatError::Json { .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn load_toml() {
+        let (_, public_key, signature) = fixtures(true);
+        let toml = toml::to_string(&signature).unwrap();
+        let loaded: Sig = load(toml.as_bytes()).unwrap();
+        assert!(loaded.verify(&public_key).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn load_yaml() {
+        let (_, public_key, signature) = fixtures(true);
+        let yaml = serde_yaml::to_string(&signature).unwrap();
+        let loaded: Sig = load(yaml.as_bytes()).unwrap();
+        assert!(loaded.verify(&public_key).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "msgpack")]
+    fn load_msgpack() {
+        let (_, public_key, signature) = fixtures(true);
+        for msgpack in [
+            rmp_serde::to_vec(&signature).unwrap(),
+            rmp_serde::to_vec_named(&signature).unwrap(),
+        ] {
+            let loaded: Sig = load(&msgpack).unwrap();
+            assert!(loaded.verify(&public_key).is_ok());
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "yaml"))]
+    fn load_disabled_format() {
+        let result = load::<Sig>(b"signature: toto");
+        assert!(matches!(
+            result,
+            Err(FormatError::Disabled {
+                format: Format::Yaml
+            })
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! JSON Canonicalization Scheme ([RFC 8785](https://www.rfc-editor.org/rfc/rfc8785)), to sign json
+//! documents independently of how they are formatted
+
+use serde_json::{Number, Value};
+
+/// Canonical form of a json value: no whitespace, object members sorted by their UTF-16 keys,
+/// numbers formatted like ECMAScript does
+pub(crate) fn canonicalize(value: &Value) -> String {
+    let mut canonical = String::new();
+    write_value(value, &mut canonical);
+    canonical
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(boolean) => out.push_str(if *boolean { "true" } else { "false" }),
+        Value::Number(number) => out.push_str(&format_number(number)),
+        Value::String(string) => write_string(string, out),
+        Value::Array(array) => {
+            out.push('[');
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(element, out);
+            }
+            out.push(']');
+        }
+        Value::Object(object) => {
+            let mut members: Vec<_> = object.iter().collect();
+            members.sort_by_cached_key(|(key, _)| key.encode_utf16().collect::<Vec<_>>());
+
+            out.push('{');
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// serde_json escapes exactly like RFC 8785 requires
+fn write_string(string: &str, out: &mut String) {
+    out.push_str(&serde_json::to_string(string).expect("strings always serialize"));
+}
+
+fn format_number(number: &Number) -> String {
+    match (number.as_i64(), number.as_u64(), number.as_f64()) {
+        (Some(integer), _, _) => integer.to_string(),
+        (_, Some(integer), _) => integer.to_string(),
+        (_, _, Some(float)) => format_float(float),
+        _ => unreachable!("json numbers are integers or floats"),
+    }
+}
+
+/// Format a float like `Number.prototype.toString()` of ECMAScript
+fn format_float(float: f64) -> String {
+    if float == 0.0 {
+        return "0".into();
+    }
+
+    // Shortest digits which round-trip, with exponent, ex: `1.2345e-7`
+    let scientific = format!("{:e}", float.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("has exponent");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().expect("exponent is an integer") + 1;
+
+    let formatted = if k <= n && n <= 21 {
+        format!("{digits}{}", "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        format!("{integer}.{fraction}")
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat(-n as usize))
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        let sign = if n > 0 { "+" } else { "-" };
+        format!("{first}{fraction}e{sign}{}", (n - 1).abs())
+    };
+
+    if float < 0.0 {
+        format!("-{formatted}")
+    } else {
+        formatted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc8785_example() {
+        // RFC 8785, section 3.2.2
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            canonicalize(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn sort_by_utf16() {
+        // RFC 8785, section 3.2.3
+        let value: Value = serde_json::from_str(
+            r#"{"€": "Euro Sign", "\r": "Carriage Return", "\ufb33": "Hebrew Letter Dalet With Dagesh", "1": "One", "😀": "Emoji: Grinning Face", "\u0080": "Control", "ö": "Latin Small Letter O With Diaeresis"}"#,
+        )
+        .unwrap();
+        let canonical = canonicalize(&value);
+        let order: Vec<&str> = ["\r", "1", "\u{80}", "ö", "€", "😀", "\u{fb33}"].into();
+        let positions: Vec<usize> = order
+            .iter()
+            .map(|key| {
+                let member = format!("{}:", serde_json::to_string(key).unwrap());
+                canonical.find(&member).unwrap()
+            })
+            .collect();
+        assert!(positions.is_sorted());
+    }
+
+    #[test]
+    fn numbers() {
+        for (float, expected) in [
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (1.5e-7, "1.5e-7"),
+            (0.000001, "0.000001"),
+            (-0.5, "-0.5"),
+            (-0.0, "0"),
+        ] {
+            assert_eq!(format_float(float), expected);
+        }
+    }
+}
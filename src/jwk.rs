@@ -0,0 +1,261 @@
+//! Publish public keys as JSON Web Keys ([RFC 8037](https://www.rfc-editor.org/rfc/rfc8037)), and
+//! verify signatures against a JWK Set
+//!
+//! Keys are `OKP` keys on the `Ed25519` curve. The `kid` member is the key number, encoded in
+//! base64url like the `kid` header of [JWTs](crate::jwt): a [`Signature`] is verified by the key of
+//! the set with the key number of its signer.
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//! use sigserlic::jwk::JwkSet;
+//!
+//! let builder = sigserlic::SignatureBuilder::<_, ()>::new(String::from("toto"));
+//! let signature = signing_key.sign(builder).unwrap();
+//!
+//! // Publish the current keys, for example at `/.well-known/jwks.json`
+//! let mut set = JwkSet::default();
+//! set.push(sigserlic::PublicKey::from(signing_key).to_jwk());
+//! let json = serde_json::to_string(&set).unwrap();
+//!
+//! // Consumers fetch the set, and verify signatures with it
+//! let set: JwkSet = serde_json::from_str(&json).unwrap();
+//! let message = set.verify(signature).unwrap();
+//! assert_eq!(message.data(), "toto");
+//! ```
+
+use crate::error::SignatureError;
+use crate::key::key_id;
+use crate::{KeyMetadata, Message, PublicKey, Signature};
+use base64ct::Encoding;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+/// Key type of Ed25519 keys
+const KEY_TYPE: &str = "OKP";
+
+/// Curve of Ed25519 keys
+const CURVE: &str = "Ed25519";
+
+/// Algorithm of Ed25519 signatures in JOSE
+const ALGORITHM: &str = "EdDSA";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Public key as a JSON Web Key
+pub struct Jwk {
+    kty: String,
+    crv: String,
+    x: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+    #[serde(rename = "use", default, skip_serializing_if = "Option::is_none")]
+    usage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alg: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Set of JSON Web Keys, the document published by services for consumers to fetch
+pub struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors when converting JSON Web Keys or verifying signatures with a JWK Set
+pub enum JwkError {
+    #[snafu(display("unsupported key type `{kty}` on curve `{crv}`"))]
+    /// Key is not an `OKP` key on the `Ed25519` curve
+    KeyType {
+        /// Key type of the key
+        kty: String,
+        /// Curve of the key
+        crv: String,
+    },
+    #[snafu(display("unsupported algorithm `{algorithm}`"))]
+    /// Key is meant for another algorithm than `EdDSA`
+    Algorithm {
+        /// Algorithm of the key
+        algorithm: String,
+    },
+    #[snafu(display("decoding base64"))]
+    /// Key is not valid base64url
+    Base64 {
+        /// Original error
+        source: base64ct::Error,
+    },
+    #[snafu(display("key is not 32 bytes"))]
+    /// Key has the wrong length for Ed25519
+    KeyLength,
+    #[snafu(display("no key with identifier `{kid}` in set"))]
+    /// None of the keys of the set made the signature
+    UnknownKey {
+        /// Key identifier of the signer
+        kid: String,
+    },
+    #[snafu(display("verify signature"))]
+    /// Signature could not be decoded, or is not authentic
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+}
+
+impl Jwk {
+    /// Key identifier, the key number of keys made by sigserlic
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+}
+
+impl<C> PublicKey<C> {
+    /// Convert to a JSON Web Key, with the key number as `kid`
+    pub fn to_jwk(&self) -> Jwk {
+        Jwk {
+            kty: KEY_TYPE.into(),
+            crv: CURVE.into(),
+            x: base64ct::Base64UrlUnpadded::encode_string(&self.key_bytes()),
+            kid: Some(key_id(self.keynum())),
+            usage: Some("sig".into()),
+            alg: Some(ALGORITHM.into()),
+        }
+    }
+
+    /// Convert from a JSON Web Key, with default metadata
+    ///
+    /// The key number is taken from `kid` when it is one, otherwise it is derived from the key.
+    pub fn from_jwk(jwk: &Jwk) -> Result<Self, JwkError> {
+        if jwk.kty != KEY_TYPE || jwk.crv != CURVE {
+            return Err(JwkError::KeyType {
+                kty: jwk.kty.clone(),
+                crv: jwk.crv.clone(),
+            });
+        }
+        if let Some(algorithm) = &jwk.alg
+            && algorithm != ALGORITHM
+        {
+            return Err(JwkError::Algorithm {
+                algorithm: algorithm.clone(),
+            });
+        }
+
+        let key = base64ct::Base64UrlUnpadded::decode_vec(&jwk.x).context(Base64Snafu)?;
+        let key = <[u8; 32]>::try_from(key).map_err(|_| JwkError::KeyLength)?;
+        let keynum = jwk
+            .kid
+            .as_deref()
+            .and_then(|kid| base64ct::Base64UrlUnpadded::decode_vec(kid).ok())
+            .and_then(|kid| <[u8; 8]>::try_from(kid).ok());
+
+        Ok(Self::from_key_bytes(&key, keynum))
+    }
+}
+
+impl JwkSet {
+    /// Add a key to the set
+    pub fn push(&mut self, jwk: Jwk) {
+        self.keys.push(jwk);
+    }
+
+    /// Keys of the set
+    pub fn keys(&self) -> &[Jwk] {
+        &self.keys
+    }
+
+    /// Find the key with the identifier `kid`
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|jwk| jwk.kid() == Some(kid))
+    }
+
+    /// Verify a signature with the key of the set which made it, selected by `kid`
+    pub fn verify<'de, T, C>(&self, signature: Signature<T, C>) -> Result<Message<T>, JwkError>
+    where
+        T: Serialize + Deserialize<'de>,
+    {
+        let signer = signature
+            .signature()
+            .context(SignatureSnafu)?
+            .signer_keynum();
+        let kid = key_id(signer);
+        let jwk = self.find(&kid).context(UnknownKeySnafu { kid })?;
+        let public_key = PublicKey::<()>::from_jwk(jwk)?;
+
+        signature.verify(&public_key).context(SignatureSnafu)
+    }
+}
+
+impl<C> FromIterator<PublicKey<C>> for JwkSet {
+    fn from_iter<I: IntoIterator<Item = PublicKey<C>>>(iter: I) -> Self {
+        Self {
+            keys: iter.into_iter().map(|key| key.to_jwk()).collect(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::{COMMENTED_SIGNING_KEY_JSON, other_public_key};
+    use crate::{SignatureBuilder, SigningKey};
+
+    #[test]
+    fn rfc8037_key() {
+        // RFC 8037, appendix A.2
+        let jwk: Jwk = serde_json::from_str(
+            r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#,
+        )
+        .unwrap();
+        let public_key = PublicKey::<()>::from_jwk(&jwk).unwrap();
+
+        let exported = public_key.to_jwk();
+        assert_eq!(exported.x, jwk.x);
+        assert_eq!(exported.kid(), Some(key_id(public_key.keynum()).as_str()));
+    }
+
+    #[test]
+    fn roundtrip_keeps_keynum() {
+        let signing_key: SigningKey<String> =
+            serde_json::from_str(COMMENTED_SIGNING_KEY_JSON).unwrap();
+        let public_key = PublicKey::from(signing_key);
+        let json = serde_json::to_string(&public_key.to_jwk()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"kty":"OKP","crv":"Ed25519","x":"yaDyljmdcpUXX96KyiAUDacFzdWN3MNHEcuE83VyxWY","kid":"XIpOm-CJl9c","use":"sig","alg":"EdDSA"}"#
+        );
+
+        let imported = PublicKey::<()>::from_jwk(&serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(imported.keynum(), public_key.keynum());
+    }
+
+    #[test]
+    fn verify_with_set() {
+        let signing_key: SigningKey<String> =
+            serde_json::from_str(COMMENTED_SIGNING_KEY_JSON).unwrap();
+        let builder = SignatureBuilder::<_, ()>::new(String::from("toto"));
+        let signature = signing_key.sign(builder).unwrap();
+        let json = serde_json::to_string(&signature).unwrap();
+
+        let other = other_public_key();
+        let set = JwkSet::from_iter([other]);
+        let signature: Signature<String, ()> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            set.verify(signature).unwrap_err(),
+            JwkError::UnknownKey { .. }
+        ));
+
+        let mut set: JwkSet = serde_json::from_str(&serde_json::to_string(&set).unwrap()).unwrap();
+        set.push(PublicKey::from(signing_key).to_jwk());
+        let signature: Signature<String, ()> = serde_json::from_str(&json).unwrap();
+        assert_eq!(set.verify(signature).unwrap().data(), "toto");
+    }
+
+    #[test]
+    fn not_ed25519() {
+        let jwk: Jwk = serde_json::from_str(
+            r#"{"kty":"OKP","crv":"X25519","x":"hSDwCYkwp1R0i33ctD73Wg2_Og0mOBr066SpjqqbTmo"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            PublicKey::<()>::from_jwk(&jwk).unwrap_err(),
+            JwkError::KeyType { .. }
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+//! Export signatures as JSON Web Tokens, for services which only understand JWTs
+//!
+//! Tokens are JWS in compact serialization, signed with the `EdDSA` algorithm by the same key
+//! pair: the [`Message`] timestamp becomes the `iat` claim, its expiration the `exp` claim, its
+//! nonce the `jti` claim, and the data, which must serialize as a map, is flattened in the other
+//! claims. The `kid` header is the key number, encoded in base64url.
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//!     admin: bool,
+//! }
+//!
+//! let builder = sigserlic::SignatureBuilder::<_, ()>::new(Claims {
+//!     sub: "toto".into(),
+//!     admin: false,
+//! });
+//! let signature = signing_key.sign(builder).unwrap();
+//!
+//! let jwt = signature.to_jwt(&signing_key).unwrap();
+//!
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//! let message = public_key.verify_jwt::<Claims>(&jwt).unwrap();
+//! assert_eq!(message.data().sub, "toto");
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::error::{SignatureError, TimestampError};
+use crate::key::key_id;
+use crate::{KeyMetadata, Message, Nonce, PublicKey, Signature};
+use base64ct::Encoding;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+/// Algorithm of Ed25519 signatures in JOSE
+const ALGORITHM: &str = "EdDSA";
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    typ: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+#[cfg(feature = "sign")]
+/// Names of the claims holding the [`Message`] fields, which data cannot use
+const RESERVED_CLAIMS: [&str; 3] = ["iat", "exp", "jti"];
+
+/// Claims of a token, where `jti` is a [`Nonce`] in tokens made by the crate, any string in others
+#[derive(Serialize, Deserialize)]
+struct Claims<T, Jti = Nonce> {
+    iat: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jti: Option<Jti>,
+    #[serde(flatten)]
+    data: T,
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when producing or verifying a JSON Web Token
+pub enum JwtError {
+    #[snafu(display("token is not made of 3 parts separated by dots"))]
+    /// Not a JWS in compact serialization
+    Format,
+    #[snafu(display("decoding base64"))]
+    /// Part of the token is not valid base64url
+    Base64 {
+        /// Original error
+        source: base64ct::Error,
+    },
+    #[snafu(display("header or claims of token"))]
+    /// Failed to encode or decode header or claims with json, data must be a map of claims
+    Json {
+        /// Original error
+        source: serde_json::Error,
+    },
+    #[snafu(display("unsupported algorithm `{algorithm}`"))]
+    /// Token is not signed with `EdDSA`
+    Algorithm {
+        /// Algorithm of the token
+        algorithm: String,
+    },
+    #[snafu(display("token was made by another key"))]
+    /// Key identifier of the token is not the one of the key
+    KeyMismatch,
+    #[snafu(display("data has a field `{claim}`, which is a reserved claim"))]
+    /// Data has a field named `iat`, `exp` or `jti`, which would be mistaken for the [`Message`]
+    /// claims
+    ReservedClaim {
+        /// Name of the field
+        claim: String,
+    },
+    #[snafu(display("decoding signature"))]
+    /// The signature to convert could not be decoded
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+    #[snafu(display("invalid timestamp in claims"))]
+    /// `iat` or `exp` claim is out of range
+    Timestamp {
+        /// Original error
+        source: TimestampError,
+    },
+    #[snafu(display("verify signature with public key"))]
+    /// Failed to use key to verify authenticity of token
+    Verify {
+        /// Original error
+        source: libsignify::Error,
+    },
+}
+
+#[cfg(feature = "sign")]
+fn encode_part<T: Serialize>(part: &T) -> Result<String, JwtError> {
+    let json = serde_json::to_vec(part).context(JsonSnafu)?;
+    Ok(base64ct::Base64UrlUnpadded::encode_string(&json))
+}
+
+impl<T: Serialize, C> Signature<T, C> {
+    #[cfg(feature = "sign")]
+    /// Convert the signed message to a JWT, signed again with `EdDSA`
+    ///
+    /// `signing_key` has to be the one which made the signature, otherwise
+    /// [`JwtError::KeyMismatch`] is returned. Timestamps are truncated to seconds. The comment is
+    /// not part of the token. Fields of the data named `iat`, `exp` or `jti` are refused with
+    /// [`JwtError::ReservedClaim`].
+    pub fn to_jwt<S>(&self, signing_key: &SigningKey<S>) -> Result<String, JwtError> {
+        let signer = self.signature().context(SignatureSnafu)?.signer_keynum();
+        if signer != signing_key.keynum() {
+            return Err(JwtError::KeyMismatch);
+        }
+
+        let header = Header {
+            alg: ALGORITHM.into(),
+            typ: Some("JWT".into()),
+            kid: Some(key_id(signer)),
+        };
+        let message = &self.signed_artifact;
+        let data = serde_json::to_value(&message.data).context(JsonSnafu)?;
+        if let Some(claim) = data.as_object().and_then(|data| {
+            RESERVED_CLAIMS
+                .into_iter()
+                .find(|claim| data.contains_key(*claim))
+        }) {
+            return Err(JwtError::ReservedClaim {
+                claim: claim.into(),
+            });
+        }
+        let claims = Claims {
+            iat: message.timestamp.as_second(),
+            exp: message.expiration.map(|e| e.as_second()),
+            jti: message.nonce,
+            data,
+        };
+
+        let signing_input = format!("{}.{}", encode_part(&header)?, encode_part(&claims)?);
+        let signature = signing_key.sign_raw(signing_input.as_bytes());
+        Ok(format!(
+            "{signing_input}.{}",
+            base64ct::Base64UrlUnpadded::encode_string(&signature)
+        ))
+    }
+}
+
+impl<C> PublicKey<C> {
+    /// Verify a JWT signed with `EdDSA` by the signing key of this public key, and get the
+    /// message out of its claims
+    ///
+    /// The `exp` claim becomes the expiration of the message, it is not enforced: compare it
+    /// with the current time using [`Message::is_expired_at()`]. The `jti` claim becomes the
+    /// nonce of the message when it is one, other identifiers such as UUIDs are accepted but
+    /// left out.
+    pub fn verify_jwt<T: DeserializeOwned>(&self, token: &str) -> Result<Message<T>, JwtError> {
+        let claims = self.verify_jws(token.trim())?;
+        let claims: Claims<T, String> = serde_json::from_slice(&claims).context(JsonSnafu)?;
+        let nonce = claims.jti.and_then(|jti| {
+            let bytes = base64ct::Base64::decode_vec(&jti).ok()?;
+            <[u8; Nonce::LEN]>::try_from(bytes).ok().map(Nonce::from)
+        });
+
+        Ok(Message {
+            data: claims.data,
+            timestamp: crate::timestamp::parse_timestamp(claims.iat).context(TimestampSnafu)?,
+            expiration: claims
+                .exp
+                .map(crate::timestamp::parse_timestamp)
+                .transpose()
+                .context(TimestampSnafu)?,
+            nonce,
+        })
+    }
+
+    /// Verify a JWS in compact serialization, returning its payload
+    fn verify_jws(&self, token: &str) -> Result<Vec<u8>, JwtError> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(JwtError::Format);
+        };
+
+        let decoded = base64ct::Base64UrlUnpadded::decode_vec(header).context(Base64Snafu)?;
+        let decoded: Header = serde_json::from_slice(&decoded).context(JsonSnafu)?;
+        if decoded.alg != ALGORITHM {
+            return Err(JwtError::Algorithm {
+                algorithm: decoded.alg,
+            });
+        }
+        if decoded.kid.is_some_and(|kid| kid != key_id(self.keynum())) {
+            return Err(JwtError::KeyMismatch);
+        }
+
+        let signature = base64ct::Base64UrlUnpadded::decode_vec(signature).context(Base64Snafu)?;
+        let signing_input = &token[..header.len() + 1 + payload.len()];
+        self.verify_raw(signing_input.as_bytes(), &signature)
+            .context(VerifySnafu)?;
+
+        base64ct::Base64UrlUnpadded::decode_vec(payload).context(Base64Snafu)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::SignatureBuilder;
+    use crate::test_keys::{other_public_key, other_signing_key, public_key, signing_key};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct License {
+        sub: String,
+        seats: u8,
+    }
+
+    fn signature() -> Signature<License, ()> {
+        let builder = SignatureBuilder::new(License {
+            sub: "toto".into(),
+            seats: 5,
+        })
+        .timestamp(1700000000)
+        .unwrap()
+        .expiration(1800000000)
+        .unwrap();
+        signing_key().sign(builder).unwrap()
+    }
+
+    fn decode_json(part: &str) -> serde_json::Value {
+        let json = base64ct::Base64UrlUnpadded::decode_vec(part).unwrap();
+        serde_json::from_slice(&json).unwrap()
+    }
+
+    #[test]
+    fn export() {
+        let jwt = signature().to_jwt(&signing_key()).unwrap();
+        let parts: Vec<&str> = jwt.split('.').collect();
+        assert_eq!(parts.len(), 3);
+
+        assert_eq!(
+            decode_json(parts[0]),
+            serde_json::json!({"alg": "EdDSA", "typ": "JWT", "kid": "l6436od-WIw"})
+        );
+        assert_eq!(
+            decode_json(parts[1]),
+            serde_json::json!({"iat": 1700000000, "exp": 1800000000, "sub": "toto", "seats": 5})
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let jwt = signature().to_jwt(&signing_key()).unwrap();
+        let message = public_key().verify_jwt::<License>(&jwt).unwrap();
+
+        assert_eq!(message, signature().verify(&signing_key().into()).unwrap());
+    }
+
+    #[test]
+    fn wrong_key() {
+        let jwt = signature().to_jwt(&signing_key()).unwrap();
+        let other = other_public_key();
+        assert!(matches!(
+            other.verify_jwt::<License>(&jwt).unwrap_err(),
+            JwtError::KeyMismatch
+        ));
+
+        // Converting requires the key which made the signature
+        let other: SigningKey<()> = other_signing_key();
+        assert!(matches!(
+            signature().to_jwt(&other).unwrap_err(),
+            JwtError::KeyMismatch
+        ));
+    }
+
+    #[test]
+    fn altered() {
+        let jwt = signature().to_jwt(&signing_key()).unwrap();
+        let parts: Vec<&str> = jwt.split('.').collect();
+        let claims = base64ct::Base64UrlUnpadded::encode_string(
+            br#"{"iat":1700000000,"exp":1800000000,"sub":"toto","seats":50}"#,
+        );
+        let altered = format!("{}.{claims}.{}", parts[0], parts[2]);
+
+        assert!(matches!(
+            public_key().verify_jwt::<License>(&altered).unwrap_err(),
+            JwtError::Verify { .. }
+        ));
+    }
+
+    #[test]
+    fn reserved_claims() {
+        #[derive(Serialize, Deserialize)]
+        struct Data {
+            sub: String,
+            exp: u32,
+        }
+
+        let builder = SignatureBuilder::<_, ()>::new(Data {
+            sub: "toto".into(),
+            exp: 42,
+        });
+        let signature = signing_key().sign(builder).unwrap();
+        assert!(matches!(
+            signature.to_jwt(&signing_key()).unwrap_err(),
+            JwtError::ReservedClaim { claim } if claim == "exp"
+        ));
+    }
+
+    #[test]
+    fn foreign_jti() {
+        let header = encode_part(&serde_json::json!({"alg": "EdDSA"})).unwrap();
+        let claims = encode_part(&serde_json::json!({
+            "iat": 1700000000,
+            "jti": "3f2c1a5e-7b1d-4c8e-9a2f-6d4b8e1c0a97",
+            "sub": "toto",
+            "seats": 5,
+        }))
+        .unwrap();
+        let signing_input = format!("{header}.{claims}");
+        let signature = signing_key().sign_raw(signing_input.as_bytes());
+        let jwt = format!(
+            "{signing_input}.{}",
+            base64ct::Base64UrlUnpadded::encode_string(&signature)
+        );
+
+        let message = public_key().verify_jwt::<License>(&jwt).unwrap();
+        assert_eq!(message.data().sub, "toto");
+        assert_eq!(message.nonce(), None);
+    }
+
+    #[test]
+    fn invalid_token() {
+        let public_key = public_key();
+        assert!(matches!(
+            public_key.verify_jwt::<License>("toto.gateau").unwrap_err(),
+            JwtError::Format
+        ));
+
+        // {"alg":"HS256"}
+        assert!(matches!(
+            public_key
+                .verify_jwt::<License>("eyJhbGciOiJIUzI1NiJ9.e30.AAAA")
+                .unwrap_err(),
+            JwtError::Algorithm { algorithm } if algorithm == "HS256"
+        ));
+    }
+
+    #[test]
+    fn rfc8037_example() {
+        // Key and token of RFC 8037, appendix A.4, wrapped in a signify public key
+        let mut key = b"Ed\0\0\0\0\0\0\0\0".to_vec();
+        key.extend(
+            base64ct::Base64UrlUnpadded::decode_vec("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo")
+                .unwrap(),
+        );
+        let json = format!(
+            r#"{{"public_key":"{}","created_at":"2024-12-23T00:12:54Z","expired_at":null}}"#,
+            base64ct::Base64::encode_string(&key)
+        );
+        let public_key: PublicKey<()> = serde_json::from_str(&json).unwrap();
+
+        let token = "eyJhbGciOiJFZERTQSJ9.RXhhbXBsZSBvZiBFZDI1NTE5IHNpZ25pbmc.hgyY0il_MGCjP0JzlnLWG1PPOt7-09PGcvMg3AIbQR6dWbhijcNR4ki4iylGjg5BhVsPt9g7sVvpAr_MuM0KAg";
+        assert_eq!(
+            public_key.verify_jws(token).unwrap(),
+            b"Example of Ed25519 signing"
+        );
+    }
+}
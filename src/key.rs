@@ -24,3 +24,23 @@ pub enum KeyUsage {
     /// Verify signatures
     Verifying,
 }
+
+/// Key number as a string, for formats identifying keys with text, encoded in base64url
+#[cfg(feature = "std")]
+pub(crate) fn key_id(keynum: libsignify::KeyNumber) -> String {
+    use base64ct::Encoding;
+
+    base64ct::Base64UrlUnpadded::encode_string(keynum.as_ref())
+}
+
+/// Key number of keys imported from other formats, which do not have one: derived from the public
+/// key, for the signing key and public key to have the same
+#[cfg(feature = "std")]
+pub(crate) fn derive_keynum(public_key: &[u8; 32]) -> [u8; 8] {
+    use sha2::{Digest, Sha256};
+
+    let digest = Sha256::digest(public_key);
+    let mut keynum = [0; 8];
+    keynum.copy_from_slice(&digest[..8]);
+    keynum
+}
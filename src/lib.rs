@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))] // https://stackoverflow.com/a/61417700/4809297
 
@@ -159,25 +160,121 @@ assert_eq!(data.string, "Toto mange du gateau");
 ```
 */
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod activation;
+#[cfg(feature = "std")]
+pub mod armor;
+mod base64_serde;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod cose;
+#[cfg(feature = "std")]
+mod der;
+#[cfg(feature = "std")]
+pub mod did;
+#[cfg(feature = "std")]
+pub mod dsse;
+mod fields_serde;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+mod jcs;
+#[cfg(feature = "std")]
+pub mod jwk;
+#[cfg(feature = "std")]
+pub mod jwt;
 mod key;
+#[cfg(feature = "std")]
+pub mod machine;
 mod metadata;
+mod nonce;
+#[cfg(feature = "std")]
+pub mod openpgp;
+#[cfg(feature = "std")]
+pub mod paseto;
+#[cfg(feature = "std")]
+pub mod pkcs8;
 mod public_key;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod revocation;
 mod signature;
+#[cfg(feature = "sign")]
 mod signing_key;
+mod source;
+#[cfg(feature = "std")]
+pub mod ssh;
+#[cfg(test)]
+mod test_keys;
 mod timestamp;
+#[cfg(feature = "std")]
+pub mod vc;
+#[cfg(feature = "std")]
+pub mod x509;
 
 pub use key::{KeyMetadata, KeyUsage};
 pub(crate) use metadata::Metadata;
+pub use nonce::Nonce;
 pub use public_key::PublicKey;
+#[cfg(feature = "sign")]
 pub use signature::builder::SignatureBuilder;
+#[cfg(feature = "std")]
+pub use signature::license_key::LICENSE_KEY_MAX_LEN;
 pub use signature::{Message, Signature};
+#[cfg(feature = "sign")]
 pub use signing_key::SigningKey;
 
 const BINCODE_CONFIG: bincode::config::Configuration = bincode::config::standard();
 
 /// Error which can occur when using the crate
 pub mod error {
+    #[cfg(feature = "std")]
+    pub use crate::activation::ActivationError;
+    #[cfg(feature = "std")]
+    pub use crate::armor::ArmorError;
+    #[cfg(feature = "std")]
+    pub use crate::clock::ClockError;
+    #[cfg(feature = "std")]
+    pub use crate::cose::CoseError;
+    #[cfg(feature = "std")]
+    pub use crate::did::DidError;
+    #[cfg(feature = "std")]
+    pub use crate::dsse::DsseError;
+    #[cfg(feature = "std")]
+    pub use crate::format::FormatError;
+    #[cfg(feature = "std")]
+    pub use crate::jwk::JwkError;
+    #[cfg(feature = "std")]
+    pub use crate::jwt::JwtError;
+    #[cfg(feature = "std")]
+    pub use crate::machine::MachineError;
+    #[cfg(feature = "std")]
+    pub use crate::openpgp::OpenPgpError;
+    #[cfg(feature = "std")]
+    pub use crate::paseto::PasetoError;
+    #[cfg(feature = "std")]
+    pub use crate::pkcs8::Pkcs8Error;
+    #[cfg(feature = "std")]
+    pub use crate::replay::ReplayError;
+    #[cfg(feature = "std")]
+    pub use crate::revocation::RevocationError;
     pub use crate::signature::SignatureError;
+    #[cfg(feature = "sign")]
     pub use crate::signature::builder::SignatureBuilderError;
+    #[cfg(feature = "std")]
+    pub use crate::signature::license_key::LicenseKeyError;
+    #[cfg(feature = "std")]
+    pub use crate::signature::token::TokenError;
+    pub use crate::source::DependencyError;
+    #[cfg(feature = "std")]
+    pub use crate::ssh::SshError;
     pub use crate::timestamp::TimestampError;
+    #[cfg(feature = "std")]
+    pub use crate::vc::CredentialError;
+    #[cfg(feature = "std")]
+    pub use crate::x509::X509Error;
 }
@@ -0,0 +1,370 @@
+//! Bind signatures to a single machine, for node-locked licenses
+
+use crate::error::SignatureError;
+use crate::{Message, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Source of information about a machine, contributing to its [`Fingerprint`]
+pub enum Component {
+    /// Unique identifier of the installation, from `/etc/machine-id`
+    MachineId,
+    /// Name of the machine on the network
+    Hostname,
+    /// Model of the processor, from `/proc/cpuinfo`
+    CpuModel,
+}
+
+impl Component {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::MachineId => "machine_id",
+            Self::Hostname => "hostname",
+            Self::CpuModel => "cpu_model",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+struct ComponentHash(#[serde(with = "crate::base64_serde")] [u8; 32]);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Stable identity of a machine, made of salted hashes of its [`Component`]s.
+///
+/// A fingerprint can be signed inside a [`Message`], to bind a license to a single machine.
+/// Raw values never leave the machine, only their hashes are stored, along with the salt so that
+/// components added to a deserialized fingerprint are hashed the same way.
+///
+/// ```
+/// use sigserlic::machine::{Component, Fingerprint, MatchPolicy};
+///
+/// let salt = b"my product";
+/// let fingerprint = Fingerprint::new(salt)
+///     .with(Component::MachineId, "3d1219c7c4c5404aaa1f6d2a48adfda4")
+///     .with(Component::Hostname, "toto");
+///
+/// let renamed = Fingerprint::new(salt)
+///     .with(Component::MachineId, "3d1219c7c4c5404aaa1f6d2a48adfda4")
+///     .with(Component::Hostname, "gateau");
+///
+/// assert!(fingerprint.compare(&renamed, MatchPolicy::Exact).is_err());
+/// assert!(fingerprint.compare(&renamed, MatchPolicy::AtLeast(1)).is_ok());
+/// ```
+pub struct Fingerprint {
+    #[serde(with = "crate::base64_serde::vec")]
+    salt: Vec<u8>,
+    components: BTreeMap<Component, ComponentHash>,
+}
+
+impl Fingerprint {
+    /// Empty fingerprint, component values will be hashed with `salt`.
+    ///
+    /// Use a salt specific to your product, to prevent linking fingerprints across products.
+    pub fn new(salt: &[u8]) -> Self {
+        Self {
+            salt: salt.to_vec(),
+            components: BTreeMap::new(),
+        }
+    }
+
+    /// Add a component to the fingerprint, surrounding whitespace of `value` is ignored
+    pub fn with(mut self, component: Component, value: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update((self.salt.len() as u64).to_be_bytes());
+        hasher.update(&self.salt);
+        hasher.update(component.name());
+        hasher.update([0]);
+        hasher.update(value.trim());
+
+        self.components
+            .insert(component, ComponentHash(hasher.finalize().into()));
+        self
+    }
+
+    /// Components used in the fingerprint
+    pub fn components(&self) -> impl Iterator<Item = Component> + '_ {
+        self.components.keys().copied()
+    }
+
+    /// Fingerprint of the machine running this program.
+    ///
+    /// Components which cannot be read are left out, an error is returned only when none of them
+    /// are available.
+    #[cfg(target_os = "linux")]
+    pub fn current(salt: &[u8]) -> Result<Self, MachineError> {
+        let mut fingerprint = Self::new(salt);
+
+        let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+            .into_iter()
+            .find_map(|path| std::fs::read_to_string(path).ok())
+            .filter(|id| !id.trim().is_empty());
+        if let Some(machine_id) = machine_id {
+            fingerprint = fingerprint.with(Component::MachineId, &machine_id);
+        }
+
+        if let Ok(hostname) = std::fs::read_to_string("/proc/sys/kernel/hostname") {
+            fingerprint = fingerprint.with(Component::Hostname, &hostname);
+        }
+
+        if let Some(cpu_model) = std::fs::read_to_string("/proc/cpuinfo")
+            .ok()
+            .as_deref()
+            .and_then(parse_cpu_model)
+        {
+            fingerprint = fingerprint.with(Component::CpuModel, cpu_model);
+        }
+
+        match fingerprint.components.is_empty() {
+            true => Err(MachineError::NoComponent),
+            false => Ok(fingerprint),
+        }
+    }
+
+    /// Compare this fingerprint with the one of another machine, usually the current one.
+    ///
+    /// Every component of this fingerprint is looked up in `other`, those missing from it count
+    /// as mismatches: [`MatchPolicy::Exact`] requires all of them to match.
+    pub fn compare(&self, other: &Self, policy: MatchPolicy) -> Result<(), MachineError> {
+        let matching = self
+            .components
+            .iter()
+            .filter(|(component, hash)| other.components.get(component) == Some(hash))
+            .count();
+
+        let required = match policy {
+            MatchPolicy::Exact => self.components.len().max(1),
+            MatchPolicy::AtLeast(required) => required.max(1),
+        };
+
+        match matching >= required {
+            true => Ok(()),
+            false => Err(MachineError::Mismatch { matching, required }),
+        }
+    }
+}
+
+impl PartialEq for Fingerprint {
+    fn eq(&self, other: &Self) -> bool {
+        self.components == other.components
+    }
+}
+
+impl Eq for Fingerprint {}
+
+#[cfg(target_os = "linux")]
+fn parse_cpu_model(cpuinfo: &str) -> Option<&str> {
+    cpuinfo
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| matches!(key.trim(), "model name" | "Model" | "Hardware"))
+        .map(|(_, value)| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How strict the comparison of two [`Fingerprint`]s is
+pub enum MatchPolicy {
+    /// Every component of the signed fingerprint must match
+    Exact,
+    /// Tolerate hardware changes: at least this number of components must match, and at least
+    /// one. More than the signed fingerprint has never matches.
+    AtLeast(usize),
+}
+
+/// Data carrying the [`Fingerprint`] of the machine it is bound to
+pub trait MachineBound {
+    /// Fingerprint of the machine
+    fn fingerprint(&self) -> &Fingerprint;
+}
+
+impl MachineBound for Fingerprint {
+    fn fingerprint(&self) -> &Fingerprint {
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors when binding a [`Signature`] to a machine
+pub enum MachineError {
+    #[snafu(display("no component of the machine could be read"))]
+    /// Not a single component of the machine is available
+    NoComponent,
+    #[snafu(display("only {matching} components of the machine match, {required} required"))]
+    /// The fingerprint of the machine is different
+    Mismatch {
+        /// Number of components matching
+        matching: usize,
+        /// Number of components required to match
+        required: usize,
+    },
+    #[snafu(display("verifying signature"))]
+    /// The signature itself is not valid
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+}
+
+impl<'de, T: Serialize + Deserialize<'de> + MachineBound, C> Signature<T, C> {
+    /// Verify authenticity of signature with [`PublicKey`], then make sure the signed data is
+    /// bound to the machine identified by `current`
+    pub fn verify_machine<CPubKey>(
+        self,
+        public_key: &PublicKey<CPubKey>,
+        current: &Fingerprint,
+        policy: MatchPolicy,
+    ) -> Result<Message<T>, MachineError> {
+        let message = self.verify(public_key).context(SignatureSnafu)?;
+        message.data().fingerprint().compare(current, policy)?;
+        Ok(message)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::signing_key;
+    use crate::{SignatureBuilder, SigningKey};
+
+    const SALT: &[u8] = b"toto mange du gateau";
+
+    fn fingerprint(hostname: &str, cpu_model: &str) -> Fingerprint {
+        Fingerprint::new(SALT)
+            .with(Component::MachineId, "3d1219c7c4c5404aaa1f6d2a48adfda4")
+            .with(Component::Hostname, hostname)
+            .with(Component::CpuModel, cpu_model)
+    }
+
+    #[test]
+    fn same_machine() {
+        let signed = fingerprint("toto", "Intel(R) Xeon(R) Processor");
+        let current = fingerprint("toto", "Intel(R) Xeon(R) Processor\n");
+
+        assert!(signed.compare(&current, MatchPolicy::Exact).is_ok());
+    }
+
+    #[test]
+    fn salt_changes_hashes() {
+        let signed = fingerprint("toto", "Intel(R) Xeon(R) Processor");
+        let current = Fingerprint::new(b"another product")
+            .with(Component::MachineId, "3d1219c7c4c5404aaa1f6d2a48adfda4")
+            .with(Component::Hostname, "toto")
+            .with(Component::CpuModel, "Intel(R) Xeon(R) Processor");
+
+        assert!(matches!(
+            signed
+                .compare(&current, MatchPolicy::AtLeast(1))
+                .unwrap_err(),
+            MachineError::Mismatch {
+                matching: 0,
+                required: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn fuzzy_matching() {
+        let signed = fingerprint("toto", "Intel(R) Xeon(R) Processor");
+        let current = fingerprint("toto", "AMD Ryzen 9 7950X");
+
+        assert!(matches!(
+            signed.compare(&current, MatchPolicy::Exact).unwrap_err(),
+            MachineError::Mismatch {
+                matching: 2,
+                required: 3
+            }
+        ));
+        assert!(signed.compare(&current, MatchPolicy::AtLeast(2)).is_ok());
+    }
+
+    #[test]
+    fn more_components_required_than_signed() {
+        let signed = fingerprint("toto", "Intel(R) Xeon(R) Processor");
+
+        assert!(matches!(
+            signed
+                .compare(&signed, MatchPolicy::AtLeast(5))
+                .unwrap_err(),
+            MachineError::Mismatch {
+                matching: 3,
+                required: 5
+            }
+        ));
+        assert!(signed.compare(&signed, MatchPolicy::AtLeast(0)).is_ok());
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let signed = fingerprint("toto", "Intel(R) Xeon(R) Processor");
+        let json = serde_json::to_string(&signed).unwrap();
+        assert!(!json.contains("toto"));
+
+        let imported: Fingerprint = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            imported.components().collect::<Vec<_>>(),
+            [
+                Component::MachineId,
+                Component::Hostname,
+                Component::CpuModel
+            ]
+        );
+        assert!(imported.compare(&signed, MatchPolicy::Exact).is_ok());
+
+        let extended = imported.with(Component::Hostname, "gateau");
+        assert_eq!(
+            extended,
+            fingerprint("gateau", "Intel(R) Xeon(R) Processor")
+        );
+    }
+
+    #[test]
+    fn signed_fingerprint() {
+        let signing_key: SigningKey<()> = signing_key();
+        let builder = SignatureBuilder::<Fingerprint, ()>::new(fingerprint(
+            "toto",
+            "Intel(R) Xeon(R) Processor",
+        ));
+        let json = serde_json::to_string(&signing_key.sign(builder).unwrap()).unwrap();
+        let public_key = PublicKey::from(signing_key);
+
+        let signature: Signature<Fingerprint, ()> = serde_json::from_str(&json).unwrap();
+        let current = fingerprint("toto", "Intel(R) Xeon(R) Processor");
+        assert!(
+            signature
+                .verify_machine(&public_key, &current, MatchPolicy::Exact)
+                .is_ok()
+        );
+
+        let signature: Signature<Fingerprint, ()> = serde_json::from_str(&json).unwrap();
+        let current = fingerprint("gateau", "Intel(R) Xeon(R) Processor");
+        assert!(matches!(
+            signature
+                .verify_machine(&public_key, &current, MatchPolicy::Exact)
+                .unwrap_err(),
+            MachineError::Mismatch { .. }
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parse_cpuinfo() {
+        let cpuinfo =
+            "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Xeon(R) Processor\n";
+        assert_eq!(parse_cpu_model(cpuinfo), Some("Intel(R) Xeon(R) Processor"));
+        assert_eq!(parse_cpu_model("processor\t: 0\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "reads the host, which may have neither machine-id, hostname nor cpuinfo"]
+    fn current_machine_is_stable() {
+        let first = Fingerprint::current(SALT).unwrap();
+        let second = Fingerprint::current(SALT).unwrap();
+        assert!(first.compare(&second, MatchPolicy::Exact).is_ok());
+    }
+}
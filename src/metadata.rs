@@ -1,18 +1,23 @@
+#[cfg(feature = "generate")]
 use crate::error::TimestampError;
+use crate::timestamp::{Optional, Required};
 use jiff::Timestamp;
+use serde::Serialize;
+use serde::ser::SerializeStruct;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub(crate) struct Metadata<T> {
     #[serde(with = "crate::timestamp::required")]
     pub(crate) created_at: Timestamp,
 
-    #[serde(with = "crate::timestamp::optional")]
+    #[serde(default, with = "crate::timestamp::optional")]
     pub(crate) expired_at: Option<Timestamp>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) comment: Option<T>,
 }
 
+#[cfg(feature = "std")]
 impl<T> Default for Metadata<T> {
     fn default() -> Self {
         Self {
@@ -23,6 +28,7 @@ impl<T> Default for Metadata<T> {
     }
 }
 
+#[cfg(feature = "generate")]
 impl<T> Metadata<T> {
     pub fn with_comment(mut self, comment: T) -> Self {
         self.comment = Some(comment);
@@ -35,3 +41,37 @@ impl<T> Metadata<T> {
         Ok(self)
     }
 }
+
+impl<T: Serialize> Metadata<T> {
+    /// Number of fields written by [`Metadata::serialize_fields()`]
+    pub(crate) fn fields_len(&self, human_readable: bool) -> usize {
+        if self.skip_comment(human_readable) {
+            2
+        } else {
+            3
+        }
+    }
+
+    /// Write metadata as fields of the struct of a key, instead of `#[serde(flatten)]` which
+    /// formats that are not self-describing can't handle.
+    ///
+    /// A missing comment is skipped in human-readable formats only, binary formats need every
+    /// field to be present.
+    pub(crate) fn serialize_fields<S: SerializeStruct>(
+        &self,
+        state: &mut S,
+        human_readable: bool,
+    ) -> Result<(), S::Error> {
+        state.serialize_field("created_at", &Required(self.created_at))?;
+        state.serialize_field("expired_at", &Optional(self.expired_at))?;
+        if self.skip_comment(human_readable) {
+            state.skip_field("comment")
+        } else {
+            state.serialize_field("comment", &self.comment)
+        }
+    }
+
+    fn skip_comment(&self, human_readable: bool) -> bool {
+        human_readable && self.comment.is_none()
+    }
+}
@@ -0,0 +1,53 @@
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(transparent)]
+/// Random value used only once, encoded in base64
+pub struct Nonce(#[serde(with = "crate::base64_serde")] [u8; Self::LEN]);
+
+impl Nonce {
+    /// Size of a nonce, in bytes
+    pub const LEN: usize = 16;
+
+    /// Generate a new random nonce
+    ///
+    /// ```
+    /// use sigserlic::Nonce;
+    ///
+    /// assert_ne!(Nonce::generate(), Nonce::generate());
+    /// ```
+    #[cfg(feature = "generate")]
+    pub fn generate() -> Self {
+        use rand_core::RngCore;
+
+        let mut bytes = [0; Self::LEN];
+        rand_core::OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    /// Raw bytes of the nonce
+    pub fn as_bytes(&self) -> &[u8; Self::LEN] {
+        &self.0
+    }
+}
+
+impl From<[u8; Nonce::LEN]> for Nonce {
+    fn from(bytes: [u8; Nonce::LEN]) -> Self {
+        Self(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde() {
+        let nonce = Nonce::from([0xde, 0xad, 0xba, 0xed, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        let json = serde_json::to_string(&nonce).unwrap();
+        assert_eq!(json, r#""3q267QAAAAAAAAAAAAAAAQ==""#);
+        assert_eq!(serde_json::from_str::<Nonce>(&json).unwrap(), nonce);
+
+        assert!(serde_json::from_str::<Nonce>(r#""3q267Q==""#).is_err());
+    }
+}
@@ -0,0 +1,461 @@
+//! Export keys and make detached signatures in OpenPGP ([RFC 4880](https://www.rfc-editor.org/rfc/rfc4880)),
+//! for repositories and tools which only accept it, such as `gpg --verify`
+//!
+//! Keys are v4 `EdDSA` keys (Ed25519 "legacy" of [RFC 9580](https://www.rfc-editor.org/rfc/rfc9580)),
+//! created at the creation of the key: the fingerprint of a key never changes. The user ID is the
+//! comment of the key, which is then required.
+//!
+//! OpenPGP implementations refuse user IDs which are not certified by the key itself: to be
+//! imported with `gpg --import`, the public key must be exported from the signing key with
+//! [`SigningKey::to_openpgp_public_key()`].
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<String>::generate().with_comment("Toto <toto@example.com>".into());
+//! let public_key = signing_key.to_openpgp_public_key().unwrap();
+//! assert!(public_key.starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----\n"));
+//!
+//! let signature = signing_key.sign_openpgp(b"toto mange du gateau").unwrap();
+//! assert!(signature.starts_with("-----BEGIN PGP SIGNATURE-----\n"));
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::{KeyMetadata, PublicKey};
+#[cfg(feature = "sign")]
+use jiff::Timestamp;
+use sha1::Sha1;
+use sha2::Digest;
+#[cfg(feature = "sign")]
+use sha2::Sha256;
+use snafu::Snafu;
+use std::fmt::Display;
+
+#[cfg(feature = "sign")]
+/// Label of armored public keys
+const PUBLIC_KEY_LABEL: &str = "PGP PUBLIC KEY BLOCK";
+
+#[cfg(feature = "sign")]
+/// Label of armored signatures
+const SIGNATURE_LABEL: &str = "PGP SIGNATURE";
+
+/// Version of keys and signatures
+const VERSION: u8 = 4;
+
+/// Public key algorithm `EdDSA`
+const EDDSA: u8 = 22;
+
+#[cfg(feature = "sign")]
+/// Hash algorithm SHA-256
+const SHA256: u8 = 8;
+
+/// Encoded object identifier of the Ed25519 curve, 1.3.6.1.4.1.11591.15.1
+const ED25519_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+
+#[cfg(feature = "sign")]
+/// Packet tags
+const SIGNATURE_TAG: u8 = 2;
+const PUBLIC_KEY_TAG: u8 = 6;
+const USER_ID_TAG: u8 = 13;
+
+#[cfg(feature = "sign")]
+/// Signature types
+const BINARY_DOCUMENT: u8 = 0x00;
+#[cfg(feature = "sign")]
+const POSITIVE_CERTIFICATION: u8 = 0x13;
+
+#[cfg(feature = "sign")]
+/// Signature subpacket types
+const SIGNATURE_CREATION_TIME: u8 = 2;
+#[cfg(feature = "sign")]
+const KEY_EXPIRATION_TIME: u8 = 9;
+#[cfg(feature = "sign")]
+const ISSUER: u8 = 16;
+#[cfg(feature = "sign")]
+const KEY_FLAGS: u8 = 27;
+#[cfg(feature = "sign")]
+const ISSUER_FINGERPRINT: u8 = 33;
+
+#[cfg(feature = "sign")]
+/// Key flags: certify other keys, sign data
+const CERTIFY_AND_SIGN: u8 = 0x03;
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors when exporting keys or making signatures in OpenPGP
+pub enum OpenPgpError {
+    #[snafu(display("key has no comment to use as user ID"))]
+    /// The comment of the key is not set
+    MissingUserId,
+    #[snafu(display("timestamp does not fit in OpenPGP"))]
+    /// Timestamp is before 1970 or after 2106
+    Timestamp,
+}
+
+/// Timestamp in seconds, on 4 bytes
+fn timestamp(seconds: i64) -> Result<[u8; 4], OpenPgpError> {
+    u32::try_from(seconds)
+        .map(u32::to_be_bytes)
+        .map_err(|_| OpenPgpError::Timestamp)
+}
+
+/// Encode a packet, with a new format header
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0xc0 | tag];
+    match body.len() {
+        length @ 0..192 => packet.push(length as u8),
+        length @ 192..8384 => {
+            let length = length - 192;
+            packet.extend_from_slice(&[(length >> 8) as u8 + 192, length as u8]);
+        }
+        length => {
+            packet.push(0xff);
+            packet.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// Encode a multiprecision integer, prefixed by its length in bits
+fn mpi(bytes: &[u8]) -> Vec<u8> {
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+    let bytes = &bytes[skip..];
+    let bits = match bytes.first() {
+        Some(first) => 8 * bytes.len() - first.leading_zeros() as usize,
+        None => 0,
+    };
+    [(bits as u16).to_be_bytes().as_slice(), bytes].concat()
+}
+
+#[cfg(feature = "sign")]
+/// Encode a signature subpacket
+fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+    [&[data.len() as u8 + 1, kind], data].concat()
+}
+
+/// Body of the v4 public key packet
+fn key_body(key: &[u8; 32], created_at: i64) -> Result<Vec<u8>, OpenPgpError> {
+    let mut body = vec![VERSION];
+    body.extend_from_slice(&timestamp(created_at)?);
+    body.push(EDDSA);
+    body.push(ED25519_OID.len() as u8);
+    body.extend_from_slice(ED25519_OID);
+    body.extend_from_slice(&mpi(&[&[0x40], key.as_slice()].concat()));
+    Ok(body)
+}
+
+/// Key packet as hashed for fingerprints and certifications
+fn hashed_key(key_body: &[u8]) -> Vec<u8> {
+    [
+        &[0x99],
+        (key_body.len() as u16).to_be_bytes().as_slice(),
+        key_body,
+    ]
+    .concat()
+}
+
+impl<C: Display> PublicKey<C> {
+    /// Export as a transferable public key, in binary: the public key packet followed by the
+    /// user ID packet, made of the comment
+    ///
+    /// The user ID is not certified, see the [module documentation](self).
+    pub fn to_openpgp(&self) -> Result<Vec<u8>, OpenPgpError> {
+        let user_id = self.comment().ok_or(OpenPgpError::MissingUserId)?;
+        let body = key_body(&self.key_bytes(), self.created_at())?;
+
+        Ok([
+            packet(PUBLIC_KEY_TAG, &body),
+            packet(USER_ID_TAG, user_id.to_string().as_bytes()),
+        ]
+        .concat())
+    }
+}
+
+impl<C> PublicKey<C> {
+    /// Fingerprint of the OpenPGP key, its last 8 bytes are the key ID
+    pub fn openpgp_fingerprint(&self) -> Result<[u8; 20], OpenPgpError> {
+        let body = key_body(&self.key_bytes(), self.created_at())?;
+        Ok(Sha1::digest(hashed_key(&body)).into())
+    }
+}
+
+#[cfg(feature = "sign")]
+impl<C> SigningKey<C> {
+    /// Body of the key packet, and fingerprint of the key
+    fn openpgp_key(&self) -> Result<(Vec<u8>, [u8; 20]), OpenPgpError> {
+        let body = key_body(&self.secret_key.public().key(), self.created_at())?;
+        let fingerprint = Sha1::digest(hashed_key(&body)).into();
+        Ok((body, fingerprint))
+    }
+
+    /// Make a v4 signature packet, over `prefix` followed by the signature fields
+    fn openpgp_signature(
+        &self,
+        kind: u8,
+        fingerprint: &[u8; 20],
+        mut hashed_subpackets: Vec<u8>,
+        prefix: &[u8],
+    ) -> Result<Vec<u8>, OpenPgpError> {
+        hashed_subpackets.splice(
+            0..0,
+            [
+                subpacket(
+                    SIGNATURE_CREATION_TIME,
+                    &timestamp(Timestamp::now().as_second())?,
+                ),
+                subpacket(
+                    ISSUER_FINGERPRINT,
+                    &[&[VERSION], fingerprint.as_slice()].concat(),
+                ),
+            ]
+            .concat(),
+        );
+
+        let mut hashed = vec![VERSION, kind, EDDSA, SHA256];
+        hashed.extend_from_slice(&(hashed_subpackets.len() as u16).to_be_bytes());
+        hashed.extend_from_slice(&hashed_subpackets);
+
+        let mut hasher = Sha256::new();
+        hasher.update(prefix);
+        hasher.update(&hashed);
+        hasher.update([VERSION, 0xff]);
+        hasher.update((hashed.len() as u32).to_be_bytes());
+        let digest = hasher.finalize();
+        let signature = self.sign_raw(&digest);
+
+        let unhashed = subpacket(ISSUER, &fingerprint[12..]);
+        let mut body = hashed;
+        body.extend_from_slice(&(unhashed.len() as u16).to_be_bytes());
+        body.extend_from_slice(&unhashed);
+        body.extend_from_slice(&digest[..2]);
+        body.extend_from_slice(&mpi(&signature[..32]));
+        body.extend_from_slice(&mpi(&signature[32..]));
+
+        Ok(packet(SIGNATURE_TAG, &body))
+    }
+
+    /// Make a detached signature of `data`, in ASCII armor, `-----BEGIN PGP SIGNATURE-----`
+    ///
+    /// ```sh
+    /// gpg --verify data.asc data
+    /// ```
+    pub fn sign_openpgp(&self, data: &[u8]) -> Result<String, OpenPgpError> {
+        let (_, fingerprint) = self.openpgp_key()?;
+        let signature = self.openpgp_signature(BINARY_DOCUMENT, &fingerprint, Vec::new(), data)?;
+        Ok(crate::armor::encode(SIGNATURE_LABEL, &[], &signature).expect("no headers"))
+    }
+}
+
+#[cfg(feature = "sign")]
+impl<C: Display> SigningKey<C> {
+    /// Export the public key in ASCII armor, `-----BEGIN PGP PUBLIC KEY BLOCK-----`, with the
+    /// user ID made of the comment, certified by the key
+    ///
+    /// The key expires along with the signing key, if it does.
+    pub fn to_openpgp_public_key(&self) -> Result<String, OpenPgpError> {
+        let user_id = self.comment().ok_or(OpenPgpError::MissingUserId)?;
+        let user_id = user_id.to_string();
+        let (body, fingerprint) = self.openpgp_key()?;
+
+        let mut subpackets = subpacket(KEY_FLAGS, &[CERTIFY_AND_SIGN]);
+        if let Some(expired_at) = self.expired_at() {
+            let validity = timestamp(expired_at - self.created_at())?;
+            subpackets.extend(subpacket(KEY_EXPIRATION_TIME, &validity));
+        }
+        let prefix = [
+            hashed_key(&body).as_slice(),
+            &[0xb4],
+            &(user_id.len() as u32).to_be_bytes(),
+            user_id.as_bytes(),
+        ]
+        .concat();
+        let certification =
+            self.openpgp_signature(POSITIVE_CERTIFICATION, &fingerprint, subpackets, &prefix)?;
+
+        let public_key = [
+            packet(PUBLIC_KEY_TAG, &body),
+            packet(USER_ID_TAG, user_id.as_bytes()),
+            certification,
+        ]
+        .concat();
+        Ok(crate::armor::encode(PUBLIC_KEY_LABEL, &[], &public_key).expect("no headers"))
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::other_signing_key_with;
+
+    /// Key whose comment is an OpenPGP user ID
+    fn user_key() -> SigningKey<String> {
+        other_signing_key_with(None, Some("Toto <toto@example.com>".into()))
+    }
+
+    /// Packets of armored content, as `(tag, body)`
+    fn packets(armored: &str, label: &'static str) -> Vec<(u8, Vec<u8>)> {
+        let (_, mut bytes) = crate::armor::decode(label, armored).unwrap();
+        let mut packets = Vec::new();
+        while !bytes.is_empty() {
+            let tag = bytes[0] & 0x3f;
+            let (length, header) = match bytes[1] {
+                length @ 0..192 => (length as usize, 2),
+                first @ 192..224 => (((first as usize - 192) << 8) + bytes[2] as usize + 192, 3),
+                _ => unreachable!("small packets"),
+            };
+            let rest = bytes.split_off(header + length);
+            packets.push((tag, bytes[header..].to_vec()));
+            bytes = rest;
+        }
+        packets
+    }
+
+    /// Check a signature packet against the hash prefix, returns its hashed subpackets
+    fn verify(signature: &[u8], prefix: &[u8], public_key: &PublicKey<String>) -> Vec<u8> {
+        assert_eq!(signature[..4], [VERSION, signature[1], EDDSA, SHA256]);
+        let hashed_len = 6 + u16::from_be_bytes([signature[4], signature[5]]) as usize;
+        let hashed = &signature[..hashed_len];
+        let unhashed_len = u16::from_be_bytes([signature[hashed_len], signature[hashed_len + 1]]);
+        let rest = &signature[hashed_len + 2 + unhashed_len as usize..];
+
+        let mut hasher = Sha256::new();
+        hasher.update(prefix);
+        hasher.update(hashed);
+        hasher.update([VERSION, 0xff]);
+        hasher.update((hashed.len() as u32).to_be_bytes());
+        let digest = hasher.finalize();
+        assert_eq!(rest[..2], digest[..2]);
+
+        // MPIs lose leading zeros, put them back
+        let mut signature = [0; 64];
+        let mut mpis = &rest[2..];
+        for half in signature.chunks_mut(32) {
+            let length = (u16::from_be_bytes([mpis[0], mpis[1]]) as usize).div_ceil(8);
+            half[32 - length..].copy_from_slice(&mpis[2..2 + length]);
+            mpis = &mpis[2 + length..];
+        }
+        assert!(mpis.is_empty());
+        public_key.verify_raw(&digest, &signature).unwrap();
+
+        hashed[6..].to_vec()
+    }
+
+    #[test]
+    fn key_packet() {
+        let public_key = PublicKey::from(user_key());
+        let bytes = public_key.to_openpgp().unwrap();
+        let body = key_body(&public_key.key_bytes(), public_key.created_at()).unwrap();
+
+        assert_eq!(bytes[..2], [0xc6, 51]);
+        assert_eq!(
+            body[..10],
+            [4, 0x67, 0x68, 0x9f, 0x0b, 22, 9, 0x2b, 0x06, 0x01]
+        );
+        assert_eq!(body[16..19], [0x01, 0x07, 0x40]);
+        assert_eq!(body[19..], public_key.key_bytes());
+        assert_eq!(bytes[53..55], [0xcd, 23]);
+        assert_eq!(&bytes[55..], b"Toto <toto@example.com>");
+
+        assert_eq!(
+            public_key.openpgp_fingerprint().unwrap(),
+            <[u8; 20]>::from(Sha1::digest(hashed_key(&body)))
+        );
+    }
+
+    #[test]
+    fn certified_public_key() {
+        let signing_key = user_key();
+        let armored = signing_key.to_openpgp_public_key().unwrap();
+        let public_key = PublicKey::from(signing_key);
+        let packets = packets(&armored, PUBLIC_KEY_LABEL);
+        assert_eq!(
+            packets.iter().map(|(tag, _)| *tag).collect::<Vec<_>>(),
+            [PUBLIC_KEY_TAG, USER_ID_TAG, SIGNATURE_TAG]
+        );
+
+        let user_id = &packets[1].1;
+        let prefix = [
+            hashed_key(&packets[0].1).as_slice(),
+            &[0xb4],
+            &(user_id.len() as u32).to_be_bytes(),
+            user_id,
+        ]
+        .concat();
+        let signature = &packets[2].1;
+        assert_eq!(signature[1], POSITIVE_CERTIFICATION);
+        let subpackets = verify(signature, &prefix, &public_key);
+
+        let fingerprint = public_key.openpgp_fingerprint().unwrap();
+        let issuer = subpacket(
+            ISSUER_FINGERPRINT,
+            &[&[VERSION], fingerprint.as_slice()].concat(),
+        );
+        assert!(
+            subpackets
+                .windows(issuer.len())
+                .any(|window| window == issuer)
+        );
+        let flags = subpacket(KEY_FLAGS, &[CERTIFY_AND_SIGN]);
+        assert!(subpackets.ends_with(&flags));
+    }
+
+    #[test]
+    fn expiration() {
+        let mut signing_key = user_key();
+        signing_key.metadata.expired_at = Some(Timestamp::from_second(1766445707).unwrap());
+        let armored = signing_key.to_openpgp_public_key().unwrap();
+        let signature = &packets(&armored, PUBLIC_KEY_LABEL)[2].1;
+
+        // One year after creation of the key
+        let expiration = subpacket(KEY_EXPIRATION_TIME, &31536000u32.to_be_bytes());
+        assert!(
+            signature
+                .windows(expiration.len())
+                .any(|window| window == expiration)
+        );
+    }
+
+    #[test]
+    fn detached_signature() {
+        let armored = user_key().sign_openpgp(b"toto mange du gateau").unwrap();
+        let packets = packets(&armored, SIGNATURE_LABEL);
+        assert_eq!(packets.len(), 1);
+
+        let (tag, signature) = &packets[0];
+        assert_eq!(*tag, SIGNATURE_TAG);
+        assert_eq!(signature[1], BINARY_DOCUMENT);
+        verify(
+            signature,
+            b"toto mange du gateau",
+            &PublicKey::from(user_key()),
+        );
+
+        // The issuer key ID is the end of the fingerprint
+        let fingerprint = PublicKey::from(user_key()).openpgp_fingerprint().unwrap();
+        let issuer = subpacket(ISSUER, &fingerprint[12..]);
+        assert!(
+            signature
+                .windows(issuer.len())
+                .any(|window| window == issuer)
+        );
+    }
+
+    #[test]
+    fn missing_user_id() {
+        let signing_key = other_signing_key_with::<String>(None, None);
+        assert_eq!(
+            signing_key.to_openpgp_public_key().unwrap_err(),
+            OpenPgpError::MissingUserId
+        );
+        assert_eq!(
+            PublicKey::from(signing_key).to_openpgp().unwrap_err(),
+            OpenPgpError::MissingUserId
+        );
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(mpi(&[0, 0, 0x01, 0xff]), [0, 9, 0x01, 0xff]);
+        assert_eq!(mpi(&[0x80]), [0, 8, 0x80]);
+        assert_eq!(packet(USER_ID_TAG, &[0; 200])[..3], [0xcd, 192, 8]);
+    }
+}
@@ -0,0 +1,356 @@
+//! Export signatures as PASETO `v4.public` tokens, sharing key material with token-based services
+//!
+//! The [`Message`] is stored in the claims of the token: its timestamp becomes the `iat` claim,
+//! its expiration the `exp` claim, its nonce the `jti` claim, and the data, which must serialize
+//! as a map, is flattened in the other claims. The comment of the signature goes in the footer.
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//! }
+//!
+//! let builder = sigserlic::SignatureBuilder::new(Claims { sub: "toto".into() })
+//!     .comment(String::from("license of toto"));
+//! let signature = signing_key.sign(builder).unwrap();
+//!
+//! let token = signature.to_paseto(&signing_key).unwrap();
+//! assert!(token.starts_with("v4.public."));
+//!
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//! let (message, comment) = public_key.verify_paseto::<Claims, String>(&token).unwrap();
+//! assert_eq!(message.data().sub, "toto");
+//! assert_eq!(comment.unwrap(), "license of toto");
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::KeyMetadata;
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::error::SignatureError;
+use crate::{Message, Nonce, PublicKey, Signature};
+use base64ct::Encoding;
+use jiff::Timestamp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+/// Version and purpose of tokens
+const HEADER: &str = "v4.public.";
+
+/// Size of an Ed25519 signature, appended to the claims
+const SIGNATURE_LEN: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+struct Claims<T> {
+    #[serde(with = "crate::timestamp::required")]
+    iat: Timestamp,
+    #[serde(
+        default,
+        with = "crate::timestamp::optional",
+        skip_serializing_if = "Option::is_none"
+    )]
+    exp: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jti: Option<Nonce>,
+    #[serde(flatten)]
+    data: T,
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when producing or verifying a PASETO token
+pub enum PasetoError {
+    #[snafu(display("token does not start with `{HEADER}`"))]
+    /// Not a PASETO token, or of another version or purpose
+    Header,
+    #[snafu(display("token is not made of claims and an optional footer"))]
+    /// Token has too many parts, or is too short to contain a signature
+    Format,
+    #[snafu(display("decoding base64"))]
+    /// Part of the token is not valid base64url
+    Base64 {
+        /// Original error
+        source: base64ct::Error,
+    },
+    #[snafu(display("claims or footer of token"))]
+    /// Failed to encode or decode claims or footer with json, data must be a map of claims
+    Json {
+        /// Original error
+        source: serde_json::Error,
+    },
+    #[snafu(display("signature was made by another key"))]
+    /// Signing key is not the one which produced the signature to convert
+    KeyMismatch,
+    #[snafu(display("decoding signature"))]
+    /// The signature to convert could not be decoded
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+    #[snafu(display("verify signature with public key"))]
+    /// Failed to use key to verify authenticity of token
+    Verify {
+        /// Original error
+        source: libsignify::Error,
+    },
+}
+
+/// Pre-authentication encoding, see the
+/// [specification](https://github.com/paseto-standard/paseto-spec/blob/master/docs/01-Protocol-Versions/Common.md#authentication-padding)
+fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    // Most significant bit is cleared for compatibility with languages without unsigned integers
+    let le64 = |n: usize| (n as u64 & (u64::MAX >> 1)).to_le_bytes();
+
+    let mut encoded = le64(pieces.len()).to_vec();
+    for piece in pieces {
+        encoded.extend_from_slice(&le64(piece.len()));
+        encoded.extend_from_slice(piece);
+    }
+    encoded
+}
+
+impl<T: Serialize, C: Serialize> Signature<T, C> {
+    #[cfg(feature = "sign")]
+    /// Convert the signed message to a PASETO `v4.public` token
+    ///
+    /// The token is signed again, with the same key: the key number of `signing_key` must be the
+    /// one of the signature. The comment, if set, is encoded in json in the footer: it is
+    /// authenticated by the token, but still readable without verifying it.
+    pub fn to_paseto<S>(&self, signing_key: &SigningKey<S>) -> Result<String, PasetoError> {
+        let signer = self.signature().context(SignatureSnafu)?.signer_keynum();
+        if signer != signing_key.keynum() {
+            return Err(PasetoError::KeyMismatch);
+        }
+
+        let message = &self.signed_artifact;
+        let claims = serde_json::to_vec(&Claims {
+            iat: message.timestamp,
+            exp: message.expiration,
+            jti: message.nonce,
+            data: &message.data,
+        })
+        .context(JsonSnafu)?;
+        let footer = match self.comment() {
+            Some(comment) => serde_json::to_vec(comment).context(JsonSnafu)?,
+            None => Vec::new(),
+        };
+
+        let signature = signing_key.sign_raw(&pae(&[HEADER.as_bytes(), &claims, &footer, b""]));
+
+        let mut body = claims;
+        body.extend_from_slice(&signature);
+        let mut token = format!(
+            "{HEADER}{}",
+            base64ct::Base64UrlUnpadded::encode_string(&body)
+        );
+        if !footer.is_empty() {
+            token.push('.');
+            token.push_str(&base64ct::Base64UrlUnpadded::encode_string(&footer));
+        }
+        Ok(token)
+    }
+}
+
+impl<CPubKey> PublicKey<CPubKey> {
+    /// Verify a PASETO `v4.public` token signed by the signing key of this public key, and get
+    /// the message out of its claims, with the comment out of its footer
+    ///
+    /// A token past its `exp` claim is still authentic and verifies fine, reject it with
+    /// [`Message::is_expired_at()`] if needed.
+    pub fn verify_paseto<T: DeserializeOwned, C: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<(Message<T>, Option<C>), PasetoError> {
+        let (claims, footer) = self.verify_v4_public(token.trim())?;
+
+        let claims: Claims<T> = serde_json::from_slice(&claims).context(JsonSnafu)?;
+        let comment = match footer.is_empty() {
+            true => None,
+            false => Some(serde_json::from_slice(&footer).context(JsonSnafu)?),
+        };
+
+        let message = Message {
+            data: claims.data,
+            timestamp: claims.iat,
+            expiration: claims.exp,
+            nonce: claims.jti,
+        };
+        Ok((message, comment))
+    }
+
+    /// Verify a `v4.public` token, returning its claims and footer
+    fn verify_v4_public(&self, token: &str) -> Result<(Vec<u8>, Vec<u8>), PasetoError> {
+        let token = token.strip_prefix(HEADER).ok_or(PasetoError::Header)?;
+
+        let mut parts = token.split('.');
+        let (Some(body), footer, None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(PasetoError::Format);
+        };
+        let mut claims = base64ct::Base64UrlUnpadded::decode_vec(body).context(Base64Snafu)?;
+        let footer = match footer {
+            Some(footer) => base64ct::Base64UrlUnpadded::decode_vec(footer).context(Base64Snafu)?,
+            None => Vec::new(),
+        };
+
+        let Some(length) = claims.len().checked_sub(SIGNATURE_LEN) else {
+            return Err(PasetoError::Format);
+        };
+        let signature = claims.split_off(length);
+
+        self.verify_raw(
+            &pae(&[HEADER.as_bytes(), &claims, &footer, b""]),
+            &signature,
+        )
+        .context(VerifySnafu)?;
+        Ok((claims, footer))
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::SignatureBuilder;
+    use crate::test_keys::{other_public_key, public_key, signing_key};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct License {
+        sub: String,
+        seats: u8,
+    }
+
+    fn signature(comment: Option<&str>) -> Signature<License, String> {
+        let mut builder = SignatureBuilder::new(License {
+            sub: "toto".into(),
+            seats: 5,
+        })
+        .timestamp(1700000000)
+        .unwrap()
+        .expiration(1800000000)
+        .unwrap();
+        if let Some(comment) = comment {
+            builder = builder.comment(comment.into());
+        }
+        signing_key().sign(builder).unwrap()
+    }
+
+    #[test]
+    fn pae_of_specification() {
+        assert_eq!(pae(&[]), b"\x00\x00\x00\x00\x00\x00\x00\x00");
+        assert_eq!(
+            pae(&[b"test"]),
+            b"\x01\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00test"
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let token = signature(Some("license of toto"))
+            .to_paseto(&signing_key())
+            .unwrap();
+        let (message, comment) = public_key()
+            .verify_paseto::<License, String>(&token)
+            .unwrap();
+
+        assert_eq!(
+            message,
+            signature(None).verify(&signing_key().into()).unwrap()
+        );
+        assert_eq!(comment.as_deref(), Some("license of toto"));
+    }
+
+    #[test]
+    fn without_footer() {
+        let token = signature(None).to_paseto(&signing_key()).unwrap();
+        assert_eq!(token.matches('.').count(), 2);
+
+        let claims = token.strip_prefix(HEADER).unwrap();
+        let mut claims = base64ct::Base64UrlUnpadded::decode_vec(claims).unwrap();
+        claims.truncate(claims.len() - SIGNATURE_LEN);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&claims).unwrap(),
+            serde_json::json!({
+                "iat": "2023-11-14T22:13:20Z",
+                "exp": "2027-01-15T08:00:00Z",
+                "sub": "toto",
+                "seats": 5,
+            })
+        );
+
+        let (_, comment) = public_key()
+            .verify_paseto::<License, String>(&token)
+            .unwrap();
+        assert!(comment.is_none());
+    }
+
+    #[test]
+    fn altered_footer() {
+        let token = signature(Some("license of toto"))
+            .to_paseto(&signing_key())
+            .unwrap();
+        let (body, _) = token.rsplit_once('.').unwrap();
+        let altered = format!(
+            "{body}.{}",
+            base64ct::Base64UrlUnpadded::encode_string(br#""license of titi""#)
+        );
+
+        assert!(matches!(
+            public_key()
+                .verify_paseto::<License, String>(&altered)
+                .unwrap_err(),
+            PasetoError::Verify { .. }
+        ));
+    }
+
+    #[test]
+    fn wrong_key() {
+        let token = signature(None).to_paseto(&signing_key()).unwrap();
+        let other = other_public_key();
+
+        assert!(matches!(
+            other.verify_paseto::<License, String>(&token).unwrap_err(),
+            PasetoError::Verify { .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_token() {
+        let public_key = public_key();
+        assert!(matches!(
+            public_key
+                .verify_paseto::<License, String>("v2.public.AAAA")
+                .unwrap_err(),
+            PasetoError::Header
+        ));
+        assert!(matches!(
+            public_key
+                .verify_paseto::<License, String>("v4.public.AAAA")
+                .unwrap_err(),
+            PasetoError::Format
+        ));
+    }
+
+    #[test]
+    fn specification_vector() {
+        // Test vector 4-S-1 of the PASETO specification, wrapped in a signify public key
+        let mut key = b"Ed\0\0\0\0\0\0\0\0".to_vec();
+        key.extend_from_slice(&[
+            0x1e, 0xb9, 0xdb, 0xbb, 0xbc, 0x04, 0x7c, 0x03, 0xfd, 0x70, 0x60, 0x4e, 0x00, 0x71,
+            0xf0, 0x98, 0x7e, 0x16, 0xb2, 0x8b, 0x75, 0x72, 0x25, 0xc1, 0x1f, 0x00, 0x41, 0x5d,
+            0x0e, 0x20, 0xb1, 0xa2,
+        ]);
+        let json = format!(
+            r#"{{"public_key":"{}","created_at":"2024-12-23T00:12:54Z","expired_at":null}}"#,
+            base64ct::Base64::encode_string(&key)
+        );
+        let public_key: PublicKey<()> = serde_json::from_str(&json).unwrap();
+
+        let token = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA";
+        let (claims, footer) = public_key.verify_v4_public(token).unwrap();
+        assert_eq!(
+            claims,
+            br#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#
+        );
+        assert!(footer.is_empty());
+    }
+}
@@ -1,40 +1,105 @@
-use crate::{KeyMetadata, Metadata, SigningKey};
+use crate::{KeyMetadata, Metadata};
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
-/// A key with the capability of verifying a [`Signature`](crate::Signature) emitted by a [`SigningKey`].
+#[derive(Debug)]
+/// A key with the capability of verifying a [`Signature`](crate::Signature) emitted by a [`SigningKey`](crate::SigningKey).
+///
+/// Serialized like a [`SigningKey`](crate::SigningKey), with `public_key` instead of `secret_key`.
 pub struct PublicKey<C> {
-    #[serde(with = "public_key_serde")]
     public_key: libsignify::PublicKey,
-    #[serde(flatten)]
-    metadata: Metadata<C>,
+    pub(crate) metadata: Metadata<C>,
+}
+
+/// Fields of a [`PublicKey`], with metadata inline instead of `#[serde(flatten)]`, given by name
+/// when `NAMED` (see [`fields_serde`](crate::fields_serde))
+#[derive(serde::Deserialize)]
+#[serde(rename = "PublicKey")]
+struct PublicKeyFields<C, const NAMED: bool> {
+    #[serde(deserialize_with = "public_key_serde::deserialize::<_, NAMED>")]
+    public_key: libsignify::PublicKey,
+    #[serde(deserialize_with = "crate::timestamp::required::deserialize_field::<_, NAMED>")]
+    created_at: jiff::Timestamp,
+    #[serde(
+        default,
+        deserialize_with = "crate::timestamp::optional::deserialize_field::<_, NAMED>"
+    )]
+    expired_at: Option<jiff::Timestamp>,
+    comment: Option<C>,
+}
+
+/// Fields of a [`PublicKey`] in the order they are serialized
+const FIELDS: &[&str] = &["public_key", "created_at", "expired_at", "comment"];
+
+impl<C, const NAMED: bool> From<PublicKeyFields<C, NAMED>> for PublicKey<C> {
+    fn from(fields: PublicKeyFields<C, NAMED>) -> Self {
+        Self {
+            public_key: fields.public_key,
+            metadata: Metadata {
+                created_at: fields.created_at,
+                expired_at: fields.expired_at,
+                comment: fields.comment,
+            },
+        }
+    }
+}
+
+impl<C: serde::Serialize> serde::Serialize for PublicKey<C> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let human_readable = serializer.is_human_readable();
+        let len = 1 + self.metadata.fields_len(human_readable);
+        let mut state = serializer.serialize_struct("PublicKey", len)?;
+        state.serialize_field("public_key", &public_key_serde::Field(&self.public_key))?;
+        self.metadata.serialize_fields(&mut state, human_readable)?;
+        state.end()
+    }
+}
+
+impl<'de, C: serde::Deserialize<'de>> serde::Deserialize<'de> for PublicKey<C> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::fields_serde::deserialize::<_, PublicKeyFields<C, true>, PublicKeyFields<C, false>, _>(
+            deserializer,
+            "PublicKey",
+            FIELDS,
+        )
+    }
 }
 
 mod public_key_serde {
-    use base64ct::Encoding;
+    //! Base64 string in human-readable formats, raw bytes in binary formats
+
     use libsignify::{Codeable, PublicKey};
-    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::{Deserializer, Serializer};
 
     pub fn serialize<S>(key: &PublicKey, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let encoded = base64ct::Base64::encode_string(key.as_bytes().as_ref());
-        serializer.serialize_str(&encoded)
+        crate::base64_serde::native::serialize(key.as_bytes().as_ref(), serializer)
+    }
+
+    /// Key serialized with [`serialize()`], to be used as a field of a manual implementation
+    pub struct Field<'a>(pub &'a PublicKey);
+
+    impl serde::Serialize for Field<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self.0, serializer)
+        }
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<PublicKey, D::Error>
+    pub fn deserialize<'de, D, const NAMED: bool>(deserializer: D) -> Result<PublicKey, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let key_in_base64: String = Deserialize::deserialize(deserializer)?;
         let key_in_bytes =
-            base64ct::Base64::decode_vec(&key_in_base64).map_err(serde::de::Error::custom)?;
+            crate::base64_serde::native::deserialize_field::<_, NAMED>(deserializer)?;
         PublicKey::from_bytes(&key_in_bytes).map_err(serde::de::Error::custom)
     }
 }
 
-impl<C> From<SigningKey<C>> for PublicKey<C> {
-    fn from(value: SigningKey<C>) -> Self {
+#[cfg(feature = "sign")]
+impl<C> From<crate::SigningKey<C>> for PublicKey<C> {
+    fn from(value: crate::SigningKey<C>) -> Self {
         Self {
             public_key: value.secret_key.public(),
             metadata: value.metadata,
@@ -50,6 +115,45 @@ impl<C> PublicKey<C> {
     ) -> Result<(), libsignify::Error> {
         self.public_key.verify(msg, signature)
     }
+
+    /// Raw Ed25519 public key, for formats other than signify
+    #[cfg(feature = "std")]
+    pub(crate) fn key_bytes(&self) -> [u8; 32] {
+        self.public_key.key()
+    }
+
+    /// Build a key out of a raw Ed25519 public key, with a key number derived from it unless
+    /// given, and default metadata
+    #[cfg(feature = "std")]
+    pub(crate) fn from_key_bytes(key: &[u8; 32], keynum: Option<[u8; 8]>) -> Self {
+        use libsignify::Codeable;
+
+        let keynum = keynum.unwrap_or_else(|| crate::key::derive_keynum(key));
+        let mut bytes = Vec::with_capacity(42);
+        bytes.extend_from_slice(b"Ed");
+        bytes.extend_from_slice(&keynum);
+        bytes.extend_from_slice(key);
+
+        Self {
+            public_key: libsignify::PublicKey::from_bytes(&bytes).expect("valid length"),
+            metadata: Default::default(),
+        }
+    }
+
+    /// Verify a raw Ed25519 signature, for formats other than signify
+    #[cfg(feature = "std")]
+    pub(crate) fn verify_raw(&self, msg: &[u8], signature: &[u8]) -> Result<(), libsignify::Error> {
+        use libsignify::Codeable;
+
+        // Wrap in a signify signature made by this key, key numbers are not part of other formats
+        let mut bytes = Vec::with_capacity(2 + libsignify::KeyNumber::LEN + signature.len());
+        bytes.extend_from_slice(b"Ed");
+        bytes.extend_from_slice(self.public_key.keynum().as_ref());
+        bytes.extend_from_slice(signature);
+        let signature = libsignify::Signature::from_bytes(&bytes)?;
+
+        self.public_key.verify(msg, &signature)
+    }
 }
 
 impl<C> KeyMetadata<C> for PublicKey<C> {
@@ -77,6 +181,7 @@ impl<C> KeyMetadata<C> for PublicKey<C> {
 #[cfg(test)]
 mod tests {
 
+    #[cfg(feature = "sign")]
     mod no_comment_no_expiration {
         use super::super::super::*;
 
@@ -98,6 +203,37 @@ mod tests {
 
         #[test]
         fn cbor() {
+            let cbor: [u8; 161] = [
+                164, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 88, 104, 69, 100, 66, 75,
+                0, 0, 0, 0, 251, 57, 221, 38, 179, 218, 163, 43, 186, 67, 62, 45, 126, 211, 186,
+                97, 144, 109, 204, 251, 107, 191, 180, 255, 151, 174, 55, 234, 135, 126, 88, 140,
+                219, 39, 88, 99, 248, 20, 245, 226, 99, 157, 128, 139, 223, 86, 220, 13, 20, 42,
+                187, 122, 230, 38, 125, 109, 136, 72, 156, 6, 113, 235, 112, 248, 118, 138, 65,
+                216, 165, 6, 160, 178, 208, 45, 155, 67, 51, 36, 149, 120, 90, 48, 241, 154, 127,
+                209, 127, 120, 235, 148, 35, 206, 139, 200, 176, 38, 106, 99, 114, 101, 97, 116,
+                101, 100, 95, 97, 116, 130, 26, 103, 104, 171, 6, 26, 32, 10, 14, 144, 106, 101,
+                120, 112, 105, 114, 101, 100, 95, 97, 116, 246, 103, 99, 111, 109, 109, 101, 110,
+                116, 246,
+            ];
+            let signing_key: SigningKey<()> = ciborium::from_reader(cbor.as_slice()).unwrap();
+            let signing_key_key_num = signing_key.secret_key.public().keynum();
+
+            // Convert signing key to public key, serialize
+            let public_key = PublicKey::from(signing_key);
+            let mut cbor_public_key = Vec::new();
+            ciborium::into_writer(&public_key, &mut cbor_public_key).unwrap();
+
+            // Deserialize and check key number
+            let public_key: PublicKey<()> =
+                ciborium::from_reader(cbor_public_key.as_slice()).unwrap();
+            let public_key_key_num = public_key.keynum();
+
+            assert_eq!(signing_key_key_num, public_key_key_num);
+        }
+
+        #[test]
+        fn cbor_0_2_0() {
+            // Written by 0.2.0, with strings for keys, signatures and timestamps
             let cbor: [u8; 274] = [
                 191, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 120, 208, 52, 53, 54, 52,
                 52, 50, 52, 98, 48, 48, 48, 48, 48, 48, 48, 48, 102, 98, 51, 57, 100, 100, 50, 54,
@@ -132,6 +268,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "sign")]
     mod primitive_comment_no_expiration {
         use super::super::super::*;
 
@@ -154,6 +291,38 @@ mod tests {
 
         #[test]
         fn cbor() {
+            let cbor: [u8; 172] = [
+                164, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 88, 104, 69, 100, 66, 75,
+                0, 0, 0, 0, 42, 160, 223, 39, 82, 127, 119, 19, 168, 4, 98, 232, 170, 117, 242, 65,
+                98, 123, 121, 95, 71, 254, 85, 13, 92, 138, 78, 155, 224, 137, 151, 215, 141, 238,
+                152, 200, 163, 232, 188, 0, 145, 7, 238, 255, 181, 73, 146, 102, 242, 18, 99, 17,
+                214, 213, 181, 45, 161, 249, 158, 3, 69, 67, 252, 4, 201, 160, 242, 150, 57, 157,
+                114, 149, 23, 95, 222, 138, 202, 32, 20, 13, 167, 5, 205, 213, 141, 220, 195, 71,
+                17, 203, 132, 243, 117, 114, 197, 102, 106, 99, 114, 101, 97, 116, 101, 100, 95,
+                97, 116, 130, 26, 103, 104, 159, 11, 26, 34, 24, 143, 184, 106, 101, 120, 112, 105,
+                114, 101, 100, 95, 97, 116, 246, 103, 99, 111, 109, 109, 101, 110, 116, 107, 116,
+                101, 115, 116, 105, 110, 103, 32, 107, 101, 121,
+            ];
+            let signing_key: SigningKey<String> = ciborium::from_reader(cbor.as_slice()).unwrap();
+            let signing_key_key_num = signing_key.secret_key.public().keynum();
+
+            // Convert signing key to public key, serialize
+            let public_key = PublicKey::from(signing_key);
+            let mut cbor_public_key = Vec::new();
+            ciborium::into_writer(&public_key, &mut cbor_public_key).unwrap();
+
+            // Deserialize and checks
+            let public_key: PublicKey<String> =
+                ciborium::from_reader(cbor_public_key.as_slice()).unwrap();
+            let public_key_key_num = public_key.keynum();
+
+            assert_eq!(signing_key_key_num, public_key_key_num);
+            assert_eq!(public_key.metadata.comment.as_deref(), Some("testing key"));
+        }
+
+        #[test]
+        fn cbor_0_2_0() {
+            // Written by 0.2.0, with strings for keys, signatures and timestamps
             let cbor: [u8; 295] = [
                 191, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 120, 208, 52, 53, 54, 52,
                 52, 50, 52, 98, 48, 48, 48, 48, 48, 48, 48, 48, 50, 97, 97, 48, 100, 102, 50, 55,
@@ -190,6 +359,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "sign")]
     mod struct_comment_with_expiration {
         use super::super::super::*;
 
@@ -231,6 +401,50 @@ mod tests {
 
         #[test]
         fn cbor() {
+            let cbor: [u8; 192] = [
+                164, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 88, 104, 69, 100, 66, 75,
+                0, 0, 0, 0, 196, 126, 231, 186, 139, 226, 206, 243, 117, 149, 162, 50, 198, 95, 7,
+                41, 145, 124, 183, 181, 75, 109, 216, 179, 131, 30, 31, 236, 226, 201, 129, 80,
+                146, 8, 232, 151, 214, 186, 97, 165, 230, 213, 9, 140, 9, 238, 103, 212, 0, 36, 9,
+                232, 13, 215, 100, 229, 234, 58, 200, 12, 67, 201, 128, 242, 233, 43, 21, 172, 103,
+                196, 44, 135, 55, 116, 253, 179, 71, 93, 62, 88, 130, 61, 126, 82, 194, 4, 101,
+                217, 174, 42, 185, 32, 121, 46, 219, 238, 106, 99, 114, 101, 97, 116, 101, 100, 95,
+                97, 116, 130, 26, 103, 104, 174, 143, 26, 17, 185, 31, 232, 106, 101, 120, 112,
+                105, 114, 101, 100, 95, 97, 116, 130, 26, 104, 78, 225, 128, 0, 103, 99, 111, 109,
+                109, 101, 110, 116, 163, 100, 110, 97, 109, 101, 100, 80, 104, 105, 108, 99, 97,
+                103, 101, 24, 24, 103, 97, 119, 101, 115, 111, 109, 101, 245,
+            ];
+            let signing_key: SigningKey<MyStruct> = ciborium::from_reader(cbor.as_slice()).unwrap();
+            let signing_key_key_num = signing_key.secret_key.public().keynum();
+
+            // Convert signing key to public key, serialize
+            let public_key = PublicKey::from(signing_key);
+            let mut cbor_public_key = Vec::new();
+            ciborium::into_writer(&public_key, &mut cbor_public_key).unwrap();
+
+            // Deserialize and checks
+            let public_key: PublicKey<MyStruct> =
+                ciborium::from_reader(cbor_public_key.as_slice()).unwrap();
+            let public_key_key_num = public_key.keynum();
+
+            assert_eq!(signing_key_key_num, public_key_key_num);
+            assert_eq!(
+                public_key.metadata.comment,
+                Some(MyStruct {
+                    name: "Phil".into(),
+                    age: 24,
+                    awesome: true
+                })
+            );
+            assert_eq!(
+                public_key.metadata.expired_at.map(|e| e.as_second()),
+                Some(1750000000)
+            );
+        }
+
+        #[test]
+        fn cbor_0_2_0() {
+            // Written by 0.2.0, with strings for keys, signatures and timestamps
             let cbor: [u8; 329] = [
                 191, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 120, 208, 52, 53, 54, 52,
                 52, 50, 52, 98, 48, 48, 48, 48, 48, 48, 48, 48, 99, 52, 55, 101, 101, 55, 98, 97,
@@ -279,4 +493,40 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn bincode_and_postcard() {
+        use super::PublicKey;
+        use alloc::string::String;
+
+        let json = r#"{"public_key":"RWSXrjfqh35YjHaKQdilBqCy0C2bQzMklXhaMPGaf9F/eOuUI86LyLAm","created_at":"2024-12-23T00:12:54.53753Z","expired_at":"2025-06-15T15:06:40Z","comment":"toto"}"#;
+        let public_key: PublicKey<String> = serde_json::from_str(json).unwrap();
+
+        let bytes = bincode::serde::encode_to_vec(&public_key, crate::BINCODE_CONFIG).unwrap();
+        let (decoded, _): (PublicKey<String>, _) =
+            bincode::serde::decode_from_slice(&bytes, crate::BINCODE_CONFIG).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+
+        let bytes = postcard::to_stdvec(&public_key).unwrap();
+        let decoded: PublicKey<String> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn cbor_key_is_raw_bytes() {
+        use super::PublicKey;
+        use alloc::vec::Vec;
+
+        let json = r#"{"public_key":"RWSXrjfqh35YjHaKQdilBqCy0C2bQzMklXhaMPGaf9F/eOuUI86LyLAm","created_at":"2024-12-23T00:12:54.53753Z","expired_at":null}"#;
+        let public_key: PublicKey<()> = serde_json::from_str(json).unwrap();
+
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&public_key, &mut cbor).unwrap();
+        let value: ciborium::Value = ciborium::from_reader(cbor.as_slice()).unwrap();
+        let (_, key) = &value.as_map().unwrap()[0];
+        assert_eq!(key.as_bytes().unwrap().len(), 42);
+
+        let public_key: PublicKey<()> = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(serde_json::to_string(&public_key).unwrap(), json);
+    }
 }
@@ -0,0 +1,424 @@
+//! Replay protection: accept a signature carrying a [`Nonce`] only once
+//!
+//! Signatures need a nonce, set with [`SignatureBuilder::nonce()`](crate::SignatureBuilder::nonce()),
+//! and an expiration. When verifying, nonces are remembered in a [`NonceStore`] until the
+//! signature expires, and signatures with a nonce already seen are rejected.
+//!
+//! ```
+//! use sigserlic::{Nonce, SignatureBuilder};
+//! use sigserlic::error::ReplayError;
+//! use sigserlic::replay::MemoryNonceStore;
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//!
+//! let builder = SignatureBuilder::<String, ()>::new("open the door".into())
+//!     .nonce(Nonce::generate())
+//!     .expiration(jiff::Timestamp::now().as_second() + 60)
+//!     .unwrap();
+//! let json = serde_json::to_string(&signing_key.sign(builder).unwrap()).unwrap();
+//!
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//! let mut store = MemoryNonceStore::default();
+//! let signature: sigserlic::Signature<String, ()> = serde_json::from_str(&json).unwrap();
+//! assert!(signature.verify_once(&public_key, &mut store).is_ok());
+//!
+//! // Same signature, captured and sent again
+//! let signature: sigserlic::Signature<String, ()> = serde_json::from_str(&json).unwrap();
+//! assert!(matches!(
+//!     signature.verify_once(&public_key, &mut store).unwrap_err(),
+//!     ReplayError::Replayed { .. }
+//! ));
+//! ```
+
+use crate::error::SignatureError;
+use crate::{Message, Nonce, PublicKey, Signature};
+use base64ct::Encoding;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Keep track of nonces already seen
+pub trait NonceStore {
+    /// Error when accessing the store
+    type Error: std::error::Error + 'static;
+
+    /// Remember `nonce` until `expiration`.
+    ///
+    /// Returns `false` if the nonce is already in the store. Nonces expired at `now` may be
+    /// forgotten.
+    fn insert(
+        &mut self,
+        nonce: Nonce,
+        expiration: Timestamp,
+        now: Timestamp,
+    ) -> Result<bool, Self::Error>;
+}
+
+#[derive(Debug, Default)]
+/// Nonces kept in memory, forgotten when the program exits
+pub struct MemoryNonceStore {
+    nonces: HashMap<Nonce, Timestamp>,
+}
+
+impl NonceStore for MemoryNonceStore {
+    type Error = std::convert::Infallible;
+
+    fn insert(
+        &mut self,
+        nonce: Nonce,
+        expiration: Timestamp,
+        now: Timestamp,
+    ) -> Result<bool, Self::Error> {
+        self.nonces.retain(|_, expiration| *expiration > now);
+
+        if self.nonces.contains_key(&nonce) {
+            return Ok(false);
+        }
+        self.nonces.insert(nonce, expiration);
+        Ok(true)
+    }
+}
+
+/// Nonces persisted in a file, one per line
+///
+/// The store is locked while a nonce is inserted, with a `.lock` file next to it: processes
+/// sharing the store see each other's nonces. The `.lock` file stays once created, removing it
+/// while another process holds the lock would let a third one take a lock of its own.
+pub struct FileNonceStore {
+    path: PathBuf,
+}
+
+impl FileNonceStore {
+    /// Store located at `path`, the file is created when the first nonce is inserted
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Location of the store
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// File next to the store, with `suffix` appended to its name
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(suffix);
+        path.into()
+    }
+
+    fn load(&self) -> std::io::Result<HashMap<Nonce, Timestamp>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e),
+        };
+
+        let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid nonce");
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (nonce, expiration) = line.split_once(' ').ok_or_else(invalid)?;
+                let nonce: [u8; Nonce::LEN] = base64ct::Base64::decode_vec(nonce)
+                    .ok()
+                    .and_then(|nonce| nonce.try_into().ok())
+                    .ok_or_else(invalid)?;
+                let expiration = Timestamp::from_str(expiration).map_err(|_| invalid())?;
+                Ok((Nonce::from(nonce), expiration))
+            })
+            .collect()
+    }
+
+    fn save(&self, nonces: &HashMap<Nonce, Timestamp>) -> std::io::Result<()> {
+        let content: String = nonces
+            .iter()
+            .map(|(nonce, expiration)| {
+                format!(
+                    "{} {}\n",
+                    base64ct::Base64::encode_string(nonce.as_bytes()),
+                    expiration
+                )
+            })
+            .collect();
+
+        // Write to a temporary file first, a crash must not leave a truncated store
+        let temporary = crate::clock::temporary_path(&self.path);
+        std::fs::write(&temporary, content)?;
+        std::fs::rename(&temporary, &self.path)
+    }
+}
+
+impl NonceStore for FileNonceStore {
+    type Error = std::io::Error;
+
+    fn insert(
+        &mut self,
+        nonce: Nonce,
+        expiration: Timestamp,
+        now: Timestamp,
+    ) -> Result<bool, Self::Error> {
+        // Released when dropped, another process must not insert between load and save
+        let lock = File::create(self.sibling(".lock"))?;
+        lock.lock()?;
+
+        let mut nonces = self.load()?;
+        nonces.retain(|_, expiration| *expiration > now);
+
+        if nonces.contains_key(&nonce) {
+            return Ok(false);
+        }
+        nonces.insert(nonce, expiration);
+        self.save(&nonces)?;
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when verifying a [`Signature`] against replays
+pub enum ReplayError<E: std::error::Error + 'static> {
+    #[snafu(display("verifying signature"))]
+    /// The signature itself is not valid
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+    #[snafu(display("signature has no nonce"))]
+    /// Signature was made without nonce
+    MissingNonce,
+    #[snafu(display("signature has no expiration"))]
+    /// Signature was made without expiration, its nonce would have to be remembered forever
+    MissingExpiration,
+    #[snafu(display("expired at {expiration}, now is {now}"))]
+    /// Signature is expired
+    Expired {
+        /// Expiration of the signature
+        expiration: Timestamp,
+        /// Time used for the check
+        now: Timestamp,
+    },
+    #[snafu(display("nonce has already been used"))]
+    /// Signature has already been verified
+    Replayed {
+        /// Nonce of the signature
+        nonce: Nonce,
+    },
+    #[snafu(display("accessing nonce store"))]
+    /// Failed to use the [`NonceStore`]
+    Store {
+        /// Original error
+        source: E,
+    },
+}
+
+impl<'de, T: Serialize + Deserialize<'de>, C> Signature<T, C> {
+    /// Verify authenticity of signature with [`PublicKey`], and reject it if its nonce is
+    /// already in `store`
+    ///
+    /// Signatures without expiration are rejected, since their nonces could never be forgotten.
+    /// Expired signatures are rejected as well, since their nonces may have been forgotten.
+    pub fn verify_once<CPubKey, S: NonceStore>(
+        self,
+        public_key: &PublicKey<CPubKey>,
+        store: &mut S,
+    ) -> Result<Message<T>, ReplayError<S::Error>> {
+        self.verify_once_at(public_key, store, Timestamp::now())
+    }
+
+    /// Same as [`Signature::verify_once()`], with a provided current time
+    ///
+    /// Useful with a time which cannot be rolled back, see [`TrustedTime`](crate::clock::TrustedTime).
+    pub fn verify_once_at<CPubKey, S: NonceStore>(
+        self,
+        public_key: &PublicKey<CPubKey>,
+        store: &mut S,
+        now: Timestamp,
+    ) -> Result<Message<T>, ReplayError<S::Error>> {
+        let message = self
+            .verify(public_key)
+            .map_err(|source| ReplayError::Signature { source })?;
+        let nonce = message.nonce().ok_or(ReplayError::MissingNonce)?;
+        let expiration = message.expiration().ok_or(ReplayError::MissingExpiration)?;
+        if message.is_expired_at(now) {
+            return Err(ReplayError::Expired { expiration, now });
+        }
+
+        match store.insert(nonce, expiration, now) {
+            Ok(true) => Ok(message),
+            Ok(false) => Err(ReplayError::Replayed { nonce }),
+            Err(source) => Err(ReplayError::Store { source }),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::signing_key;
+    use crate::{SignatureBuilder, SigningKey};
+
+    const TIMESTAMP_1: i64 = 1700000000;
+    const TIMESTAMP_2: i64 = 1800000000;
+    const TIMESTAMP_3: i64 = 1900000000;
+
+    fn timestamp(second: i64) -> Timestamp {
+        Timestamp::from_second(second).unwrap()
+    }
+
+    fn sign(nonce: Option<[u8; Nonce::LEN]>, expiration: Option<i64>) -> String {
+        let signing_key: SigningKey<()> = signing_key();
+        let builder = SignatureBuilder::<String, ()>::new("open the door".into())
+            .timestamp(TIMESTAMP_1)
+            .unwrap();
+        let builder = match expiration {
+            Some(expiration) => builder.expiration(expiration).unwrap(),
+            None => builder,
+        };
+        let builder = match nonce {
+            Some(nonce) => builder.nonce(nonce.into()),
+            None => builder,
+        };
+        serde_json::to_string(&signing_key.sign(builder).unwrap()).unwrap()
+    }
+
+    fn verify<S: NonceStore>(
+        json: &str,
+        store: &mut S,
+        now: i64,
+    ) -> Result<Message<String>, ReplayError<S::Error>> {
+        let signing_key: SigningKey<()> = signing_key();
+        let signature: Signature<String, ()> = serde_json::from_str(json).unwrap();
+        signature.verify_once_at(&PublicKey::from(signing_key), store, timestamp(now))
+    }
+
+    #[test]
+    fn nonce_is_signed() {
+        let json = sign(Some([1; Nonce::LEN]), Some(TIMESTAMP_3));
+        assert!(json.contains(r#""nonce":"AQEBAQEBAQEBAQEBAQEBAQ==""#));
+
+        let altered = json.replace("AQEBAQEBAQEBAQEBAQEBAQ==", "AgICAgICAgICAgICAgICAg==");
+        assert!(matches!(
+            verify(&altered, &mut MemoryNonceStore::default(), TIMESTAMP_2).unwrap_err(),
+            ReplayError::Signature { .. }
+        ));
+    }
+
+    #[test]
+    fn without_nonce() {
+        let json = sign(None, Some(TIMESTAMP_3));
+        assert!(!json.contains("nonce"));
+        assert!(matches!(
+            verify(&json, &mut MemoryNonceStore::default(), TIMESTAMP_2).unwrap_err(),
+            ReplayError::MissingNonce
+        ));
+    }
+
+    #[test]
+    fn without_expiration() {
+        let json = sign(Some([1; Nonce::LEN]), None);
+        assert!(matches!(
+            verify(&json, &mut MemoryNonceStore::default(), TIMESTAMP_2).unwrap_err(),
+            ReplayError::MissingExpiration
+        ));
+    }
+
+    #[test]
+    fn replay_in_memory() {
+        let mut store = MemoryNonceStore::default();
+        let json = sign(Some([1; Nonce::LEN]), Some(TIMESTAMP_3));
+        let other = sign(Some([2; Nonce::LEN]), Some(TIMESTAMP_3));
+
+        assert!(verify(&json, &mut store, TIMESTAMP_2).is_ok());
+        assert!(verify(&other, &mut store, TIMESTAMP_2).is_ok());
+        assert!(matches!(
+            verify(&json, &mut store, TIMESTAMP_2).unwrap_err(),
+            ReplayError::Replayed { .. }
+        ));
+    }
+
+    #[test]
+    fn expired() {
+        let mut store = MemoryNonceStore::default();
+        let json = sign(Some([1; Nonce::LEN]), Some(TIMESTAMP_2));
+
+        assert!(verify(&json, &mut store, TIMESTAMP_1).is_ok());
+        assert!(matches!(
+            verify(&json, &mut store, TIMESTAMP_3).unwrap_err(),
+            ReplayError::Expired { .. }
+        ));
+
+        // Expired nonces are forgotten
+        let other = sign(Some([2; Nonce::LEN]), Some(TIMESTAMP_3 + 1));
+        assert!(verify(&other, &mut store, TIMESTAMP_3).is_ok());
+        assert_eq!(store.nonces.len(), 1);
+    }
+
+    #[test]
+    fn replay_in_file() {
+        let path = std::env::temp_dir().join(format!("sigserlic-nonces-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let json = sign(Some([1; Nonce::LEN]), Some(TIMESTAMP_3));
+        assert!(verify(&json, &mut FileNonceStore::new(&path), TIMESTAMP_2).is_ok());
+
+        // Nonce is remembered across instances of the store
+        let mut store = FileNonceStore::new(&path);
+        let result = verify(&json, &mut store, TIMESTAMP_2);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(store.sibling(".lock")).unwrap();
+        assert!(matches!(result.unwrap_err(), ReplayError::Replayed { .. }));
+    }
+
+    #[test]
+    fn sibling_file_kept() {
+        let path =
+            std::env::temp_dir().join(format!("sigserlic-nonces-sibling-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = FileNonceStore::new(&path);
+        let sibling = store.sibling(".tmp");
+        std::fs::write(&sibling, "toto mange du gateau").unwrap();
+
+        let json = sign(Some([1; Nonce::LEN]), Some(TIMESTAMP_3));
+        let result = verify(&json, &mut store, TIMESTAMP_2);
+        let content = std::fs::read_to_string(&sibling);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&sibling).unwrap();
+        std::fs::remove_file(store.sibling(".lock")).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(content.unwrap(), "toto mange du gateau");
+    }
+
+    #[test]
+    fn concurrent_inserts_in_file() {
+        let path = std::env::temp_dir().join(format!(
+            "sigserlic-nonces-concurrent-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    FileNonceStore::new(path)
+                        .insert(
+                            [i; Nonce::LEN].into(),
+                            timestamp(TIMESTAMP_3),
+                            timestamp(TIMESTAMP_2),
+                        )
+                        .unwrap()
+                })
+            })
+            .collect();
+        assert!(threads.into_iter().all(|thread| thread.join().unwrap()));
+
+        let store = FileNonceStore::new(&path);
+        let nonces = store.load();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(store.sibling(".lock")).unwrap();
+        assert_eq!(nonces.unwrap().len(), 8);
+    }
+}
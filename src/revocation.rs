@@ -0,0 +1,192 @@
+//! Revoke single signatures, without revoking the key which produced them
+//!
+//! Every [`Signature`] has a stable [`SignatureId`]. The signing authority publishes a
+//! [`RevocationList`] of identifiers, itself signed, which is consulted when verifying.
+//!
+//! ```
+//! use sigserlic::revocation::RevocationList;
+//! use sigserlic::{Signature, SignatureBuilder};
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//!
+//! let license = signing_key.sign(SignatureBuilder::<String, ()>::new("toto".into())).unwrap();
+//! let license_id = license.id().unwrap();
+//!
+//! // Signing authority revokes the license
+//! let revocations = RevocationList::new().revoke(license_id);
+//! let revocations = signing_key.sign(SignatureBuilder::<_, ()>::new(revocations)).unwrap();
+//!
+//! // Consumer verifies the revocation list first, then the license
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//! let revocations = revocations.verify(&public_key).unwrap();
+//! assert!(license.verify_unrevoked(&public_key, &revocations).is_err());
+//! ```
+
+use crate::error::SignatureError;
+use crate::{Message, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+/// Stable identifier of a [`Signature`]: digest of its raw signature bytes, encoded in base64
+pub struct SignatureId(#[serde(with = "crate::base64_serde")] [u8; 32]);
+
+impl SignatureId {
+    /// Raw bytes of the identifier
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl<T, C> Signature<T, C> {
+    /// Stable identifier of the signature, which can be used to revoke it
+    pub fn id(&self) -> Result<SignatureId, SignatureError> {
+        use libsignify::Codeable;
+
+        let signature = self.signature()?;
+        Ok(SignatureId(Sha256::digest(signature.as_bytes()).into()))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Identifiers of revoked signatures, meant to be signed by the authority
+pub struct RevocationList {
+    revoked: BTreeSet<SignatureId>,
+}
+
+impl RevocationList {
+    /// Empty list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a signature to the list
+    pub fn revoke(mut self, id: SignatureId) -> Self {
+        self.revoked.insert(id);
+        self
+    }
+
+    /// Whether a signature is in the list
+    pub fn is_revoked(&self, id: &SignatureId) -> bool {
+        self.revoked.contains(id)
+    }
+
+    /// Identifiers of revoked signatures
+    pub fn iter(&self) -> impl Iterator<Item = &SignatureId> {
+        self.revoked.iter()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors when verifying a [`Signature`] against a [`RevocationList`]
+pub enum RevocationError {
+    #[snafu(display("verifying signature"))]
+    /// The signature itself is not valid
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+    #[snafu(display("signature has been revoked"))]
+    /// The signature is in the revocation list
+    Revoked {
+        /// Identifier of the signature
+        id: SignatureId,
+    },
+}
+
+impl<'de, T: Serialize + Deserialize<'de>, C> Signature<T, C> {
+    /// Verify authenticity of signature with [`PublicKey`], and make sure it was not revoked
+    ///
+    /// The revocation list is a [`Message`], obtained by verifying the signature of the list.
+    pub fn verify_unrevoked<CPubKey>(
+        self,
+        public_key: &PublicKey<CPubKey>,
+        revocations: &Message<RevocationList>,
+    ) -> Result<Message<T>, RevocationError> {
+        let id = self.id().context(SignatureSnafu)?;
+        if revocations.data().is_revoked(&id) {
+            return Err(RevocationError::Revoked { id });
+        }
+
+        self.verify(public_key).context(SignatureSnafu)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::SignatureBuilder;
+    use crate::test_keys::{public_key, signing_key};
+
+    const SIGNATURE_JSON: &str = r#"{"signed_artifact":{"data":"toto mange du gateau","timestamp":"2023-11-14T22:13:20Z","expiration":null},"signature":"RWSXrjfqh35YjFYE0S3ovNmiatN3eMHcBTqA+Qjl3P2togZWlNsvMR+V4DpKpEzj4rXecooNquf2GcHoARDKLMhV0+gdX+bWqAg=","comment":"toto"}"#;
+
+    fn license(customer: &str) -> Signature<String, ()> {
+        let builder = SignatureBuilder::new(customer.into())
+            .timestamp(1700000000)
+            .unwrap();
+        signing_key().sign(builder).unwrap()
+    }
+
+    fn revocations(list: RevocationList) -> Message<RevocationList> {
+        let signature = signing_key()
+            .sign(SignatureBuilder::<_, ()>::new(list))
+            .unwrap();
+        signature.verify(&public_key()).unwrap()
+    }
+
+    #[test]
+    fn stable_id() {
+        let signature: Signature<String, String> = serde_json::from_str(SIGNATURE_JSON).unwrap();
+        assert_eq!(
+            serde_json::to_string(&signature.id().unwrap()).unwrap(),
+            r#""4tPthxT+xWnhX/wgYrNkrybrO/T3JIP8JjrMynBkMZc=""#
+        );
+
+        // Comment is not signed, it does not change the identifier
+        let other: Signature<String, ()> =
+            serde_json::from_str(&SIGNATURE_JSON.replace(r#","comment":"toto""#, "")).unwrap();
+        assert_eq!(other.id(), signature.id());
+
+        assert_ne!(license("toto").id(), license("titi").id());
+    }
+
+    #[test]
+    fn revoked() {
+        let revoked = license("toto");
+        let revocations = revocations(RevocationList::new().revoke(revoked.id().unwrap()));
+
+        assert_eq!(
+            revoked
+                .verify_unrevoked(&public_key(), &revocations)
+                .unwrap_err(),
+            RevocationError::Revoked {
+                id: license("toto").id().unwrap()
+            }
+        );
+    }
+
+    #[test]
+    fn not_revoked() {
+        let revocations = revocations(RevocationList::new().revoke(license("toto").id().unwrap()));
+
+        let message = license("titi")
+            .verify_unrevoked(&public_key(), &revocations)
+            .unwrap();
+        assert_eq!(message.data(), "titi");
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let list = RevocationList::new()
+            .revoke(license("toto").id().unwrap())
+            .revoke(license("titi").id().unwrap());
+        let json = serde_json::to_string(&list).unwrap();
+
+        let imported: RevocationList = serde_json::from_str(&json).unwrap();
+        assert!(imported.is_revoked(&license("toto").id().unwrap()));
+        assert!(imported.is_revoked(&license("titi").id().unwrap()));
+        assert_eq!(imported.iter().count(), 2);
+    }
+}
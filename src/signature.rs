@@ -1,21 +1,113 @@
+#[cfg(feature = "sign")]
 pub(crate) mod builder;
-
-use crate::PublicKey;
+#[cfg(feature = "std")]
+pub(crate) mod compact;
+#[cfg(feature = "std")]
+pub(crate) mod license_key;
+#[cfg(feature = "std")]
+pub(crate) mod token;
+
+use crate::{Nonce, PublicKey};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use base64ct::Encoding;
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq)]
 /// Content signed by a [`SigningKey`](crate::SigningKey)
 pub struct Message<T> {
-    data: T,
+    pub(crate) data: T,
+    pub(crate) timestamp: Timestamp,
+    pub(crate) expiration: Option<Timestamp>,
+    pub(crate) nonce: Option<Nonce>,
+}
 
-    #[serde(with = "crate::timestamp::required")]
+/// Fields of a [`Message`], given by name when `NAMED` (see [`fields_serde`](crate::fields_serde))
+#[derive(Deserialize)]
+#[serde(rename = "Message")]
+struct MessageFields<T, const NAMED: bool> {
+    data: T,
+    #[serde(deserialize_with = "crate::timestamp::required::deserialize_field::<_, NAMED>")]
     timestamp: Timestamp,
-
-    #[serde(with = "crate::timestamp::optional")]
+    #[serde(
+        default,
+        deserialize_with = "crate::timestamp::optional::deserialize_field::<_, NAMED>"
+    )]
     expiration: Option<Timestamp>,
+    #[serde(default)]
+    nonce: Option<Nonce>,
+}
+
+impl<T, const NAMED: bool> From<MessageFields<T, NAMED>> for Message<T> {
+    fn from(fields: MessageFields<T, NAMED>) -> Self {
+        Self {
+            data: fields.data,
+            timestamp: fields.timestamp,
+            expiration: fields.expiration,
+            nonce: fields.nonce,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Message<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::fields_serde::deserialize::<_, MessageFields<T, true>, MessageFields<T, false>, _>(
+            deserializer,
+            "Message",
+            &["data", "timestamp", "expiration", "nonce"],
+        )
+    }
+}
+
+impl<T: Serialize> Serialize for Message<T> {
+    /// The nonce is skipped when absent in human-readable formats only, binary formats need every
+    /// field to be present.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use crate::timestamp::{Optional, Required};
+        use serde::ser::SerializeStruct;
+
+        let skip_nonce = serializer.is_human_readable() && self.nonce.is_none();
+        let len = if skip_nonce { 3 } else { 4 };
+        let mut state = serializer.serialize_struct("Message", len)?;
+        state.serialize_field("data", &self.data)?;
+        state.serialize_field("timestamp", &Required(self.timestamp))?;
+        state.serialize_field("expiration", &Optional(self.expiration))?;
+        if skip_nonce {
+            state.skip_field("nonce")?;
+        } else {
+            state.serialize_field("nonce", &self.nonce)?;
+        }
+        state.end()
+    }
+}
+
+/// Representation of a [`Message`] which is signed, encoded with [`Bincode`](bincode)
+///
+/// It must never change for existing signatures to remain valid: data goes through plain bincode,
+/// timestamps are strings and the nonce is skipped when absent, like signatures made before nonces
+/// existed.
+#[derive(Serialize)]
+struct SignedMessage<'a, T> {
+    data: &'a T,
+    timestamp: String,
+    expiration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<Nonce>,
+}
+
+impl<T: Serialize> Message<T> {
+    /// Bytes signed by a [`SigningKey`](crate::SigningKey)
+    pub(crate) fn signed_bytes(&self) -> Result<Vec<u8>, bincode::error::EncodeError> {
+        let message = SignedMessage {
+            data: &self.data,
+            timestamp: self.timestamp.to_string(),
+            expiration: self.expiration.map(|expiration| expiration.to_string()),
+            nonce: self.nonce,
+        };
+        bincode::serde::encode_to_vec(&message, crate::BINCODE_CONFIG)
+    }
 }
 
 impl<T> Message<T> {
@@ -33,20 +125,117 @@ impl<T> Message<T> {
     pub fn expiration(&self) -> Option<Timestamp> {
         self.expiration
     }
+
+    /// Nonce making the signature unique, if set
+    pub fn nonce(&self) -> Option<Nonce> {
+        self.nonce
+    }
+
+    /// Whether the message is expired at `now`, never if there is no expiration
+    pub fn is_expired_at(&self, now: Timestamp) -> bool {
+        self.expiration.is_some_and(|expiration| expiration <= now)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
 /// Content produced by [`SignatureBuilder`](crate::SignatureBuilder), signed by a [`SigningKey`](crate::SigningKey)
 pub struct Signature<T, C> {
     /// The signed artifact
-    signed_artifact: Message<T>,
+    pub(crate) signed_artifact: Message<T>,
     /// Base64 signature
     signature: String,
     /// Untrusted comment
-    #[serde(skip_serializing_if = "Option::is_none")]
     comment: Option<C>,
 }
 
+/// Fields of a [`Signature`], given by name when `NAMED` (see [`fields_serde`](crate::fields_serde))
+#[derive(Deserialize)]
+#[serde(rename = "Signature")]
+struct SignatureFields<T, C, const NAMED: bool> {
+    signed_artifact: Message<T>,
+    #[serde(deserialize_with = "signature_serde::deserialize::<_, NAMED>")]
+    signature: String,
+    comment: Option<C>,
+}
+
+impl<T, C, const NAMED: bool> From<SignatureFields<T, C, NAMED>> for Signature<T, C> {
+    fn from(fields: SignatureFields<T, C, NAMED>) -> Self {
+        Self {
+            signed_artifact: fields.signed_artifact,
+            signature: fields.signature,
+            comment: fields.comment,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>, C: Deserialize<'de>> Deserialize<'de> for Signature<T, C> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::fields_serde::deserialize::<
+            _,
+            SignatureFields<T, C, true>,
+            SignatureFields<T, C, false>,
+            _,
+        >(
+            deserializer,
+            "Signature",
+            &["signed_artifact", "signature", "comment"],
+        )
+    }
+}
+
+impl<T: Serialize, C: Serialize> Serialize for Signature<T, C> {
+    /// The comment is skipped when absent in human-readable formats only, binary formats need
+    /// every field to be present. The signature is raw bytes in binary formats.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let human_readable = serializer.is_human_readable();
+        let skip_comment = human_readable && self.comment.is_none();
+        let len = if skip_comment { 2 } else { 3 };
+        let mut state = serializer.serialize_struct("Signature", len)?;
+        state.serialize_field("signed_artifact", &self.signed_artifact)?;
+        state.serialize_field("signature", &signature_serde::Field(&self.signature))?;
+        if skip_comment {
+            state.skip_field("comment")?;
+        } else {
+            state.serialize_field("comment", &self.comment)?;
+        }
+        state.end()
+    }
+}
+
+mod signature_serde {
+    //! Signature kept in base64, but written as raw bytes in binary formats
+
+    use alloc::string::String;
+    use base64ct::Encoding;
+    use serde::{Deserializer, Serializer};
+
+    /// Base64 signature serialized as raw bytes in binary formats
+    pub struct Field<'a>(pub &'a str);
+
+    impl serde::Serialize for Field<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                return serializer.serialize_str(self.0);
+            }
+            let bytes = base64ct::Base64::decode_vec(self.0).map_err(serde::ser::Error::custom)?;
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D, const NAMED: bool>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            return serde::Deserialize::deserialize(deserializer);
+        }
+        let bytes = crate::base64_serde::native::deserialize_field::<_, NAMED>(deserializer)?;
+        Ok(base64ct::Base64::encode_string(&bytes))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Snafu)]
 /// Errors when manipulting a [`Signature`]
 pub enum SignatureError {
@@ -54,7 +243,8 @@ pub enum SignatureError {
     /// Failed to parse bytes of signature
     Signature {
         /// Original error
-        source: libsignify::Error,
+        #[snafu(source(from(libsignify::Error, Into::into)))]
+        source: crate::source::DependencyError<libsignify::Error>,
     },
     #[snafu(display("decoding base64"))]
     /// Failed to decode base64 encoded signature
@@ -65,11 +255,16 @@ pub enum SignatureError {
     #[snafu(display("encoding message in binary format"))]
     /// Failed to decode message with [`Bincode`](bincode)
     Bincode,
+    #[cfg(feature = "std")]
+    #[snafu(display("encoding signature in CBOR"))]
+    /// Failed to encode or decode a signature in its compact form with CBOR
+    Cbor,
     #[snafu(display("verify signature with public key"))]
     /// Failed to use key to verify authenticity of message
     Verify {
         /// Original error
-        source: libsignify::Error,
+        #[snafu(source(from(libsignify::Error, Into::into)))]
+        source: crate::source::DependencyError<libsignify::Error>,
     },
 }
 
@@ -81,9 +276,10 @@ impl<'de, T: Serialize + Deserialize<'de>, C> Signature<T, C> {
     ) -> Result<Message<T>, SignatureError> {
         let signature = self.signature()?;
 
-        let message_bytes =
-            bincode::serde::encode_to_vec(&self.signed_artifact, crate::BINCODE_CONFIG)
-                .map_err(|_| SignatureError::Bincode)?;
+        let message_bytes = self
+            .signed_artifact
+            .signed_bytes()
+            .map_err(|_| SignatureError::Bincode)?;
 
         public_key
             .verify(&message_bytes, &signature)
@@ -91,7 +287,9 @@ impl<'de, T: Serialize + Deserialize<'de>, C> Signature<T, C> {
 
         Ok(self.signed_artifact)
     }
+}
 
+impl<T, C> Signature<T, C> {
     /// Decode signature encoded in base64
     pub fn signature(&self) -> Result<libsignify::Signature, SignatureError> {
         use libsignify::Codeable;
@@ -110,6 +308,8 @@ impl<'de, T: Serialize + Deserialize<'de>, C> Signature<T, C> {
 mod tests {
     use super::*;
     use crate::KeyMetadata;
+    use crate::source::DependencyError;
+    use alloc::vec;
 
     const PUBLIC_KEY_JSON: &str = r#"{"public_key":"RWSXrjfqh35YjHaKQdilBqCy0C2bQzMklXhaMPGaf9F/eOuUI86LyLAm","created_at":"2024-12-23T00:12:54.53753Z","expired_at":null}"#;
     const PUBLIC_KEY2_JSON: &str = r#"{"public_key":"RWQnJUuDaiWf2BAemrs2IhCFqOIW6Ivotz6JpSAq4ch55WC/rz/fq0mY","created_at":"2024-12-23T16:39:25.85933Z","expired_at":null}"#;
@@ -131,7 +331,7 @@ mod tests {
         assert_eq!(
             signature.verify(&pubkey).unwrap_err(),
             SignatureError::Verify {
-                source: libsignify::Error::BadSignature
+                source: DependencyError(libsignify::Error::BadSignature)
             }
         )
     }
@@ -146,14 +346,100 @@ mod tests {
         assert_eq!(
             signature.verify(&wrong_pubkey).unwrap_err(),
             SignatureError::Verify {
-                source: libsignify::Error::MismatchedKey {
+                source: DependencyError(libsignify::Error::MismatchedKey {
                     expected: good_pubkey.keynum(),
                     found: wrong_pubkey.keynum()
-                }
+                })
             }
         );
     }
 
+    #[test]
+    fn binary_formats_use_raw_bytes_and_integers() {
+        use ciborium::Value;
+        use libsignify::Codeable;
+
+        let json = r#"{"signed_artifact":{"data":"toto mange du gateau","timestamp":"2023-11-14T22:13:20Z","expiration":null},"signature":"RWSXrjfqh35YjFYE0S3ovNmiatN3eMHcBTqA+Qjl3P2togZWlNsvMR+V4DpKpEzj4rXecooNquf2GcHoARDKLMhV0+gdX+bWqAg="}"#;
+        let signature: Signature<String, ()> = serde_json::from_str(json).unwrap();
+
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&signature, &mut cbor).unwrap();
+        let value: Value = ciborium::from_reader(cbor.as_slice()).unwrap();
+        let field = |value: &Value, name: &str| {
+            let map = value.as_map().unwrap();
+            map.iter()
+                .find(|(key, _)| key.as_text() == Some(name))
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+
+        assert_eq!(
+            field(&value, "signature").as_bytes().unwrap(),
+            &signature.signature().unwrap().as_bytes()
+        );
+        let timestamp = field(&field(&value, "signed_artifact"), "timestamp");
+        assert_eq!(timestamp, Value::Array(vec![TIMESTAMP_1.into(), 0.into()]));
+
+        // Still valid after a round-trip, and unchanged in json
+        let signature: Signature<String, ()> = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(serde_json::to_string(&signature).unwrap(), json);
+        let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+        assert!(signature.verify(&pubkey).is_ok());
+    }
+
+    #[test]
+    fn verify_from_postcard() {
+        let json = r#"{"signed_artifact":{"data":"toto mange du gateau","timestamp":"2023-11-14T22:13:20Z","expiration":null},"signature":"RWSXrjfqh35YjFYE0S3ovNmiatN3eMHcBTqA+Qjl3P2togZWlNsvMR+V4DpKpEzj4rXecooNquf2GcHoARDKLMhV0+gdX+bWqAg="}"#;
+        let signature: Signature<String, ()> = serde_json::from_str(json).unwrap();
+        let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+        let pubkey = postcard::to_stdvec(&pubkey).unwrap();
+        let signature = postcard::to_stdvec(&signature).unwrap();
+
+        let pubkey: PublicKey<()> = postcard::from_bytes(&pubkey).unwrap();
+        let signature: Signature<String, ()> = postcard::from_bytes(&signature).unwrap();
+        assert_eq!(
+            signature.verify(&pubkey).unwrap().data(),
+            "toto mange du gateau"
+        );
+    }
+
+    #[test]
+    fn msgpack_0_2_0() {
+        // Signatures written by 0.2.0 with strings, as maps and as arrays
+        let json = r#"{"signed_artifact":{"data":"toto mange du gateau","timestamp":"2023-11-14T22:13:20Z","expiration":null},"signature":"RWSXrjfqh35YjFYE0S3ovNmiatN3eMHcBTqA+Qjl3P2togZWlNsvMR+V4DpKpEzj4rXecooNquf2GcHoARDKLMhV0+gdX+bWqAg="}"#;
+        let named: serde_json::Value = serde_json::from_str(json).unwrap();
+        let positional = (
+            (
+                "toto mange du gateau",
+                "2023-11-14T22:13:20Z",
+                None::<String>,
+            ),
+            named["signature"].as_str().unwrap(),
+            None::<()>,
+        );
+        let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+
+        for msgpack in [
+            rmp_serde::to_vec(&named).unwrap(),
+            rmp_serde::to_vec(&positional).unwrap(),
+        ] {
+            let signature: Signature<String, ()> = rmp_serde::from_slice(&msgpack).unwrap();
+            assert_eq!(serde_json::to_string(&signature).unwrap(), json);
+            assert!(signature.verify(&pubkey).is_ok());
+        }
+    }
+
+    #[test]
+    fn ip_addr_0_2_0() {
+        // Signed by 0.2.0, data which is a string in json but bytes in bincode
+        let json = r#"{"signed_artifact":{"data":"10.0.0.1","timestamp":"2023-11-14T22:13:20Z","expiration":null},"signature":"RWSXrjfqh35YjPaDHXuYDhnZlAvB7F8LUnm5okI/Rx/PS4Ziu5hNpRRqePDp1U1C/0bwygPiT7/dcMs84hMI7nfRpez02ia1ogY="}"#;
+        let signature: Signature<std::net::IpAddr, ()> = serde_json::from_str(json).unwrap();
+        let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+
+        let message = signature.verify(&pubkey).unwrap();
+        assert_eq!(message.data(), &std::net::IpAddr::from([10, 0, 0, 1]));
+    }
+
     mod without_comment_without_expiration {
         use super::*;
 
@@ -170,12 +456,44 @@ mod tests {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
                     expiration: None,
+                    nonce: None,
                 })
             );
         }
 
         #[test]
         fn cbor() {
+            let cbor: [u8; 175] = [
+                163, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
+                164, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
+                32, 100, 117, 32, 103, 97, 116, 101, 97, 117, 105, 116, 105, 109, 101, 115, 116,
+                97, 109, 112, 130, 26, 101, 83, 241, 0, 0, 106, 101, 120, 112, 105, 114, 97, 116,
+                105, 111, 110, 246, 101, 110, 111, 110, 99, 101, 246, 105, 115, 105, 103, 110, 97,
+                116, 117, 114, 101, 88, 74, 69, 100, 151, 174, 55, 234, 135, 126, 88, 140, 86, 4,
+                209, 45, 232, 188, 217, 162, 106, 211, 119, 120, 193, 220, 5, 58, 128, 249, 8, 229,
+                220, 253, 173, 162, 6, 86, 148, 219, 47, 49, 31, 149, 224, 58, 74, 164, 76, 227,
+                226, 181, 222, 114, 138, 13, 170, 231, 246, 25, 193, 232, 1, 16, 202, 44, 200, 85,
+                211, 232, 29, 95, 230, 214, 168, 8, 103, 99, 111, 109, 109, 101, 110, 116, 246,
+            ];
+            let signature: Signature<String, Comment> =
+                ciborium::from_reader(cbor.as_slice()).unwrap();
+            let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+
+            assert!(signature.comment().is_none());
+            assert_eq!(
+                signature.verify(&pubkey),
+                Ok(Message {
+                    data: "toto mange du gateau".into(),
+                    timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
+                    expiration: None,
+                    nonce: None,
+                })
+            );
+        }
+
+        #[test]
+        fn cbor_0_2_0() {
+            // Written by 0.2.0, with strings for keys, signatures and timestamps
             let cbor: [u8; 199] = [
                 162, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
                 163, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
@@ -200,6 +518,7 @@ mod tests {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
                     expiration: None,
+                    nonce: None,
                 })
             );
         }
@@ -220,13 +539,46 @@ mod tests {
                 Ok(Message {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
-                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap())
+                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap()),
+                    nonce: None,
                 })
             );
         }
 
         #[test]
         fn cbor() {
+            let cbor: [u8; 181] = [
+                163, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
+                164, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
+                32, 100, 117, 32, 103, 97, 116, 101, 97, 117, 105, 116, 105, 109, 101, 115, 116,
+                97, 109, 112, 130, 26, 101, 83, 241, 0, 0, 106, 101, 120, 112, 105, 114, 97, 116,
+                105, 111, 110, 130, 26, 107, 73, 210, 0, 0, 101, 110, 111, 110, 99, 101, 246, 105,
+                115, 105, 103, 110, 97, 116, 117, 114, 101, 88, 74, 69, 100, 151, 174, 55, 234,
+                135, 126, 88, 140, 69, 90, 92, 114, 158, 255, 17, 241, 246, 80, 119, 205, 151, 58,
+                184, 42, 132, 46, 9, 234, 188, 118, 58, 121, 227, 129, 234, 44, 97, 122, 207, 201,
+                71, 69, 107, 99, 59, 187, 22, 176, 70, 218, 103, 77, 182, 168, 217, 183, 114, 59,
+                17, 63, 87, 161, 45, 138, 77, 107, 96, 204, 9, 215, 88, 15, 103, 99, 111, 109, 109,
+                101, 110, 116, 246,
+            ];
+            let signature: Signature<String, Comment> =
+                ciborium::from_reader(cbor.as_slice()).unwrap();
+            let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+
+            assert!(signature.comment().is_none());
+            assert_eq!(
+                signature.verify(&pubkey),
+                Ok(Message {
+                    data: "toto mange du gateau".into(),
+                    timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
+                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap()),
+                    nonce: None,
+                })
+            );
+        }
+
+        #[test]
+        fn cbor_0_2_0() {
+            // Written by 0.2.0, with strings for keys, signatures and timestamps
             let cbor: [u8; 219] = [
                 162, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
                 163, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
@@ -251,7 +603,8 @@ mod tests {
                 Ok(Message {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
-                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap())
+                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap()),
+                    nonce: None,
                 })
             );
         }
@@ -279,13 +632,54 @@ mod tests {
                 Ok(Message {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
-                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap())
+                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap()),
+                    nonce: None,
                 })
             );
         }
 
         #[test]
         fn cbor() {
+            let cbor: [u8; 203] = [
+                163, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
+                164, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
+                32, 100, 117, 32, 103, 97, 116, 101, 97, 117, 105, 116, 105, 109, 101, 115, 116,
+                97, 109, 112, 130, 26, 101, 83, 241, 0, 0, 106, 101, 120, 112, 105, 114, 97, 116,
+                105, 111, 110, 130, 26, 107, 73, 210, 0, 0, 101, 110, 111, 110, 99, 101, 246, 105,
+                115, 105, 103, 110, 97, 116, 117, 114, 101, 88, 74, 69, 100, 151, 174, 55, 234,
+                135, 126, 88, 140, 69, 90, 92, 114, 158, 255, 17, 241, 246, 80, 119, 205, 151, 58,
+                184, 42, 132, 46, 9, 234, 188, 118, 58, 121, 227, 129, 234, 44, 97, 122, 207, 201,
+                71, 69, 107, 99, 59, 187, 22, 176, 70, 218, 103, 77, 182, 168, 217, 183, 114, 59,
+                17, 63, 87, 161, 45, 138, 77, 107, 96, 204, 9, 215, 88, 15, 103, 99, 111, 109, 109,
+                101, 110, 116, 162, 100, 110, 97, 109, 101, 100, 84, 111, 116, 111, 106, 99, 97,
+                107, 101, 95, 101, 97, 116, 101, 114, 245,
+            ];
+            let signature: Signature<String, Comment> =
+                ciborium::from_reader(cbor.as_slice()).unwrap();
+            let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+
+            assert_eq!(
+                signature.comment(),
+                Some(Comment {
+                    name: "Toto".into(),
+                    cake_eater: true
+                })
+                .as_ref()
+            );
+            assert_eq!(
+                signature.verify(&pubkey),
+                Ok(Message {
+                    data: "toto mange du gateau".into(),
+                    timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
+                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap()),
+                    nonce: None,
+                })
+            );
+        }
+
+        #[test]
+        fn cbor_0_2_0() {
+            // Written by 0.2.0, with strings for keys, signatures and timestamps
             let cbor: [u8; 250] = [
                 163, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
                 163, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
@@ -319,7 +713,8 @@ mod tests {
                 Ok(Message {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
-                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap())
+                    expiration: Some(Timestamp::from_second(TIMESTAMP_2).unwrap()),
+                    nonce: None,
                 })
             );
         }
@@ -348,12 +743,53 @@ mod tests {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
                     expiration: None,
+                    nonce: None,
                 })
             );
         }
 
         #[test]
         fn cbor() {
+            let cbor: [u8; 197] = [
+                163, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
+                164, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
+                32, 100, 117, 32, 103, 97, 116, 101, 97, 117, 105, 116, 105, 109, 101, 115, 116,
+                97, 109, 112, 130, 26, 101, 83, 241, 0, 0, 106, 101, 120, 112, 105, 114, 97, 116,
+                105, 111, 110, 246, 101, 110, 111, 110, 99, 101, 246, 105, 115, 105, 103, 110, 97,
+                116, 117, 114, 101, 88, 74, 69, 100, 151, 174, 55, 234, 135, 126, 88, 140, 86, 4,
+                209, 45, 232, 188, 217, 162, 106, 211, 119, 120, 193, 220, 5, 58, 128, 249, 8, 229,
+                220, 253, 173, 162, 6, 86, 148, 219, 47, 49, 31, 149, 224, 58, 74, 164, 76, 227,
+                226, 181, 222, 114, 138, 13, 170, 231, 246, 25, 193, 232, 1, 16, 202, 44, 200, 85,
+                211, 232, 29, 95, 230, 214, 168, 8, 103, 99, 111, 109, 109, 101, 110, 116, 162,
+                100, 110, 97, 109, 101, 100, 84, 111, 116, 111, 106, 99, 97, 107, 101, 95, 101, 97,
+                116, 101, 114, 245,
+            ];
+            let signature: Signature<String, Comment> =
+                ciborium::from_reader(cbor.as_slice()).unwrap();
+            let pubkey: PublicKey<()> = serde_json::from_str(PUBLIC_KEY_JSON).unwrap();
+
+            assert_eq!(
+                signature.comment(),
+                Some(Comment {
+                    name: "Toto".into(),
+                    cake_eater: true
+                })
+                .as_ref()
+            );
+            assert_eq!(
+                signature.verify(&pubkey),
+                Ok(Message {
+                    data: "toto mange du gateau".into(),
+                    timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
+                    expiration: None,
+                    nonce: None,
+                })
+            );
+        }
+
+        #[test]
+        fn cbor_0_2_0() {
+            // Written by 0.2.0, with strings for keys, signatures and timestamps
             let cbor: [u8; 230] = [
                 163, 111, 115, 105, 103, 110, 101, 100, 95, 97, 114, 116, 105, 102, 97, 99, 116,
                 163, 100, 100, 97, 116, 97, 116, 116, 111, 116, 111, 32, 109, 97, 110, 103, 101,
@@ -387,6 +823,7 @@ mod tests {
                     data: "toto mange du gateau".into(),
                     timestamp: Timestamp::from_second(TIMESTAMP_1).unwrap(),
                     expiration: None,
+                    nonce: None,
                 })
             );
         }
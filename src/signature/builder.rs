@@ -1,5 +1,5 @@
 use crate::error::TimestampError;
-use crate::{Message, Signature, SigningKey};
+use crate::{Message, Nonce, Signature, SigningKey};
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
@@ -13,6 +13,8 @@ pub struct SignatureBuilder<M: Serialize, C> {
 
     expires_at: Option<Timestamp>,
 
+    nonce: Option<Nonce>,
+
     comment: Option<C>,
 }
 
@@ -48,6 +50,7 @@ impl<'de, M: Serialize + Deserialize<'de>, C> SignatureBuilder<M, C> {
             message,
             timestamp: None,
             expires_at: None,
+            nonce: None,
             comment: None,
         }
     }
@@ -66,6 +69,22 @@ impl<'de, M: Serialize + Deserialize<'de>, C> SignatureBuilder<M, C> {
         Ok(self)
     }
 
+    /// If set, this nonce **will be** signed with the message.
+    ///
+    /// Verifiers keeping track of nonces can reject a signature used more than once, see
+    /// [`Signature::verify_once()`].
+    ///
+    /// ```
+    /// use sigserlic::Nonce;
+    /// type MySignatureBuilder = sigserlic::SignatureBuilder::<&'static str, ()>;
+    ///
+    /// let builder = MySignatureBuilder::new("open the door").nonce(Nonce::generate());
+    /// ```
+    pub fn nonce(mut self, nonce: Nonce) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
     /// If set, the comment **will not be** signed
     ///
     /// See openbsd signify "untrusted comment"
@@ -96,13 +115,13 @@ impl<'de, M: Serialize + Deserialize<'de>, C> SignatureBuilder<M, C> {
         use libsignify::Codeable;
 
         let timestamp = self.timestamp.unwrap_or(Timestamp::now());
-        if let Some(expiration) = self.expires_at {
-            if expiration <= timestamp {
-                return Err(SignatureBuilderError::PastExpiration {
-                    expiration,
-                    timestamp,
-                });
-            }
+        if let Some(expiration) = self.expires_at
+            && expiration <= timestamp
+        {
+            return Err(SignatureBuilderError::PastExpiration {
+                expiration,
+                timestamp,
+            });
         }
 
         // Encode message in bytes
@@ -110,8 +129,10 @@ impl<'de, M: Serialize + Deserialize<'de>, C> SignatureBuilder<M, C> {
             data: self.message,
             timestamp,
             expiration: self.expires_at,
+            nonce: self.nonce,
         };
-        let message_bytes = bincode::serde::encode_to_vec(&message, crate::BINCODE_CONFIG)
+        let message_bytes = message
+            .signed_bytes()
             .map_err(|_| SignatureBuilderError::Bincode)?;
 
         // Sign the message with secret key, and encode to a base64 string
@@ -0,0 +1,122 @@
+//! Compact binary encoding of a [`Signature`], shared by tokens, license keys and activations
+//!
+//! The encoding is CBOR, which describes its values: data and comment may be of any type the JSON
+//! form accepts, such as `serde_json::Value`, untagged enums or `#[serde(flatten)]` structs.
+
+use super::{Base64Snafu, Message, Signature, SignatureError, SignatureSnafu};
+use crate::Nonce;
+use base64ct::Encoding;
+use jiff::Timestamp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+/// Representation of a [`Signature`] in the compact encoding
+///
+/// Fields are positional to save space, timestamps are integers and the signature is raw bytes.
+#[derive(Serialize, Deserialize)]
+struct CompactSignature<T, C>(
+    T,
+    #[serde(with = "crate::timestamp::compact::required")] Timestamp,
+    #[serde(with = "crate::timestamp::compact::optional")] Option<Timestamp>,
+    Option<Nonce>,
+    #[serde(with = "crate::base64_serde::native")] Vec<u8>,
+    Option<C>,
+);
+
+impl<T: Serialize, C: Serialize> Signature<T, C> {
+    /// Encode signature in a compact binary format
+    pub(crate) fn to_compact_bytes(&self) -> Result<Vec<u8>, SignatureError> {
+        let compact = CompactSignature(
+            &self.signed_artifact.data,
+            self.signed_artifact.timestamp,
+            self.signed_artifact.expiration,
+            self.signed_artifact.nonce,
+            base64ct::Base64::decode_vec(&self.signature).context(Base64Snafu)?,
+            self.comment.as_ref(),
+        );
+
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&compact, &mut bytes).map_err(|_| SignatureError::Cbor)?;
+        Ok(bytes)
+    }
+}
+
+impl<T: DeserializeOwned, C: DeserializeOwned> Signature<T, C> {
+    /// Decode signature from the format of [`Signature::to_compact_bytes()`]
+    pub(crate) fn from_compact_bytes(mut bytes: &[u8]) -> Result<Self, SignatureError> {
+        use libsignify::Codeable;
+
+        let CompactSignature(data, timestamp, expiration, nonce, signature, comment) =
+            ciborium::from_reader(&mut bytes).map_err(|_| SignatureError::Cbor)?;
+        if !bytes.is_empty() {
+            return Err(SignatureError::Cbor);
+        }
+
+        let signature = libsignify::Signature::from_bytes(&signature)
+            .context(SignatureSnafu)?
+            .as_bytes();
+
+        Ok(Self {
+            signed_artifact: Message {
+                data,
+                timestamp,
+                expiration,
+                nonce,
+            },
+            signature: base64ct::Base64::encode_string(&signature),
+            comment,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PublicKey;
+    use crate::test_keys::{SIGNATURE_JSON, public_key};
+
+    #[test]
+    fn roundtrip() {
+        let signature: Signature<String, String> = serde_json::from_str(SIGNATURE_JSON).unwrap();
+        let bytes = signature.to_compact_bytes().unwrap();
+
+        let decoded: Signature<String, String> = Signature::from_compact_bytes(&bytes).unwrap();
+        assert_eq!(decoded.signature, signature.signature);
+        assert_eq!(decoded.comment(), Some(&"toto".into()));
+
+        let pubkey: PublicKey<()> = public_key();
+        assert_eq!(decoded.verify(&pubkey), signature.verify(&pubkey));
+    }
+
+    #[test]
+    fn subsecond_timestamp() {
+        let json = r#"{"signed_artifact":{"data":{"name":"Toto","action":"eat cake","age":42,"awesome":true},"timestamp":"2024-12-24T16:20:43.44666Z","expiration":null},"signature":"RWRZeb8cfPFAOgRE7OeT1PjkIz5tio+AIq2aw9IPlg3Zwcdnomp9OSHpFq7JUF86e4AiFjpRCjolDkNwHCxb3RvJWLk15USiowg=","comment":"don't trust me, but the cake is awful!"}"#;
+        let public_key: PublicKey<String> = serde_json::from_str(r#"{"public_key":"RWRZeb8cfPFAOmGKehlrQh2xGCuz1G11bL+rVOJUtzB+bMpa2CxnTnEb","created_at":"2024-12-24T15:02:48.845298Z","expired_at":null,"comment":"testing key, do not use"}"#).unwrap();
+
+        #[derive(Serialize, Deserialize)]
+        struct Data {
+            name: String,
+            action: String,
+            age: u8,
+            awesome: bool,
+        }
+
+        let signature: Signature<Data, String> = serde_json::from_str(json).unwrap();
+        let bytes = signature.to_compact_bytes().unwrap();
+        let decoded: Signature<Data, String> = Signature::from_compact_bytes(&bytes).unwrap();
+        assert!(decoded.verify(&public_key).is_ok());
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let signature: Signature<String, String> = serde_json::from_str(SIGNATURE_JSON).unwrap();
+        let mut bytes = signature.to_compact_bytes().unwrap();
+        bytes.push(0);
+
+        assert_eq!(
+            Signature::<String, String>::from_compact_bytes(&bytes).unwrap_err(),
+            SignatureError::Cbor
+        );
+    }
+}
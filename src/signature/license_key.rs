@@ -0,0 +1,311 @@
+use super::{Signature, SignatureError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use snafu::Snafu;
+
+/// Version of the format, first byte of the key
+const LICENSE_KEY_VERSION: u8 = 1;
+
+/// Size of the checksum appended at the end of the key, in bytes
+const CHECKSUM_LEN: usize = 4;
+
+/// Maximum size of the payload inside a license key made by [`Signature::to_license_key()`], in
+/// bytes, beyond that a key is not really typable
+pub const LICENSE_KEY_MAX_LEN: usize = 128;
+
+/// Number of characters between dashes
+const GROUP_LEN: usize = 5;
+
+/// Crockford's base32 alphabet, without I, L, O and U
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors when encoding or decoding a license key
+///
+/// Those are input errors: a failure to verify the signature inside a valid key is reported by
+/// [`Signature::verify()`].
+pub enum LicenseKeyError {
+    #[snafu(display("invalid character `{character}` at position {position}"))]
+    /// Character which is not part of the alphabet
+    InvalidCharacter {
+        /// Character in the input
+        character: char,
+        /// Position of the character in the input
+        position: usize,
+    },
+    #[snafu(display("license key is too short"))]
+    /// Not enough characters for a license key
+    Length,
+    #[snafu(display("checksum does not match, the license key contains a typo"))]
+    /// The key was not typed correctly
+    Checksum,
+    #[snafu(display("unsupported version {version}"))]
+    /// Key made by an unsupported version of the format
+    Version {
+        /// Version of the key
+        version: u8,
+    },
+    #[snafu(display("payload takes {length} bytes, more than {LICENSE_KEY_MAX_LEN}"))]
+    /// Signed data, comment and signature are too large to be typed
+    TooLong {
+        /// Size of the payload
+        length: usize,
+    },
+    #[snafu(display("encoding or decoding payload"))]
+    /// Signature cannot be encoded, or the content of the key is not the expected data
+    Payload,
+    #[snafu(display("handling signature"))]
+    /// Content of the key is not a valid signature
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+}
+
+/// Report failures to encode or decode the payload apart from invalid signatures
+fn payload_error(source: SignatureError) -> LicenseKeyError {
+    match source {
+        SignatureError::Cbor => LicenseKeyError::Payload,
+        source => LicenseKeyError::Signature { source },
+    }
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::digest(bytes);
+    let mut checksum = [0; CHECKSUM_LEN];
+    checksum.copy_from_slice(&digest[..CHECKSUM_LEN]);
+    checksum
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u16, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[usize::from((buffer >> bits) & 0x1f)].into());
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)].into());
+    }
+    encoded
+}
+
+fn decode_base32(input: &str) -> Result<Vec<u8>, LicenseKeyError> {
+    let mut decoded = Vec::with_capacity(input.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u16, 0);
+    for (position, character) in input.chars().enumerate() {
+        let value = match character.to_ascii_uppercase() {
+            '-' => continue,
+            c if c.is_whitespace() => continue,
+            // Look-alike characters
+            'O' => 0,
+            'I' | 'L' => 1,
+            c => ALPHABET.iter().position(|a| char::from(*a) == c).ok_or(
+                LicenseKeyError::InvalidCharacter {
+                    character,
+                    position,
+                },
+            )? as u16,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+impl<T: Serialize, C: Serialize> Signature<T, C> {
+    /// Encode the whole signature as a license key which can be typed by people, such as
+    /// `0A0S6-XQF8B-...`
+    ///
+    /// Only small data fits in a license key: the signature alone takes 74 bytes, and the whole
+    /// key is limited to [`LICENSE_KEY_MAX_LEN`] bytes. Prefer a comment-less signature, field
+    /// names of structs are part of the key as well.
+    ///
+    /// ```
+    /// # let signing_key = sigserlic::SigningKey::<()>::generate();
+    /// type MySignatureBuilder = sigserlic::SignatureBuilder::<u32, ()>;
+    ///
+    /// let builder = MySignatureBuilder::new(42).timestamp(1735311570).unwrap();
+    /// let signature = signing_key.sign(builder).unwrap();
+    /// let license_key = signature.to_license_key().unwrap();
+    /// assert!(license_key.split('-').all(|group| group.len() <= 5));
+    /// ```
+    pub fn to_license_key(&self) -> Result<String, LicenseKeyError> {
+        let compact = self.to_compact_bytes().map_err(payload_error)?;
+        if compact.len() > LICENSE_KEY_MAX_LEN {
+            return Err(LicenseKeyError::TooLong {
+                length: compact.len(),
+            });
+        }
+
+        let mut bytes = Vec::with_capacity(1 + compact.len() + CHECKSUM_LEN);
+        bytes.push(LICENSE_KEY_VERSION);
+        bytes.extend_from_slice(&compact);
+        bytes.extend_from_slice(&checksum(&bytes));
+
+        let encoded = encode_base32(&bytes);
+        let groups: Vec<&str> = encoded
+            .as_bytes()
+            .chunks(GROUP_LEN)
+            .map(|group| std::str::from_utf8(group).expect("base32 is ascii"))
+            .collect();
+        Ok(groups.join("-"))
+    }
+}
+
+impl<T: DeserializeOwned, C: DeserializeOwned> Signature<T, C> {
+    /// Decode a signature from a license key produced by [`Signature::to_license_key()`]
+    ///
+    /// Case is ignored, as well as dashes and whitespace. Letters `O`, `I` and `L` are read as
+    /// the digits they look like. The signature still needs to be verified with
+    /// [`Signature::verify()`].
+    pub fn from_license_key(license_key: &str) -> Result<Self, LicenseKeyError> {
+        let bytes = decode_base32(license_key)?;
+        if bytes.len() <= 1 + CHECKSUM_LEN {
+            return Err(LicenseKeyError::Length);
+        }
+
+        let (content, expected) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(content) != expected {
+            return Err(LicenseKeyError::Checksum);
+        }
+
+        match content[0] {
+            LICENSE_KEY_VERSION => Self::from_compact_bytes(&content[1..]).map_err(payload_error),
+            version => Err(LicenseKeyError::Version { version }),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::test_keys::{public_key, signing_key};
+    use crate::{SignatureBuilder, SigningKey};
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct License {
+        seats: u8,
+        premium: bool,
+    }
+
+    fn license_key() -> String {
+        let signing_key: SigningKey<()> = signing_key();
+        let builder = SignatureBuilder::<_, ()>::new(License {
+            seats: 5,
+            premium: true,
+        })
+        .timestamp(1735311570)
+        .unwrap();
+        signing_key.sign(builder).unwrap().to_license_key().unwrap()
+    }
+
+    #[test]
+    fn base32() {
+        assert_eq!(encode_base32(b""), "");
+        assert_eq!(encode_base32(b"f"), "CR");
+        assert_eq!(encode_base32(b"foobar"), "CSQPYRK1E8");
+        assert_eq!(decode_base32("CSQPYRK1E8").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn roundtrip() {
+        let license_key = license_key();
+        assert!(license_key.len() < 220);
+        assert!(license_key.split('-').all(|group| group.len() <= GROUP_LEN));
+        assert!(
+            license_key
+                .chars()
+                .all(|c| c == '-' || ALPHABET.contains(&(c as u8)))
+        );
+
+        let signature = Signature::<License, ()>::from_license_key(&license_key).unwrap();
+        let message = signature.verify(&public_key()).unwrap();
+        assert_eq!(
+            message.data(),
+            &License {
+                seats: 5,
+                premium: true
+            }
+        );
+    }
+
+    #[test]
+    fn sloppy_typing() {
+        let license_key = license_key()
+            .to_lowercase()
+            .replace('0', "o")
+            .replace('1', "l")
+            .replace('-', " ");
+
+        let signature = Signature::<License, ()>::from_license_key(&license_key).unwrap();
+        assert!(signature.verify(&public_key()).is_ok());
+    }
+
+    #[test]
+    fn typo() {
+        let mut license_key = license_key().into_bytes();
+        license_key[7] = match license_key[7] {
+            b'A' => b'B',
+            _ => b'A',
+        };
+        let license_key = String::from_utf8(license_key).unwrap();
+
+        assert_eq!(
+            Signature::<License, ()>::from_license_key(&license_key).unwrap_err(),
+            LicenseKeyError::Checksum
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            Signature::<License, ()>::from_license_key("ABCDE-FGHUJ").unwrap_err(),
+            LicenseKeyError::InvalidCharacter {
+                character: 'U',
+                position: 9
+            }
+        );
+        assert_eq!(
+            Signature::<License, ()>::from_license_key("ABCDE").unwrap_err(),
+            LicenseKeyError::Length
+        );
+
+        // Valid checksum, unknown version
+        let mut bytes = vec![2, 0, 0, 0];
+        bytes.extend_from_slice(&checksum(&bytes));
+        assert_eq!(
+            Signature::<License, ()>::from_license_key(&encode_base32(&bytes)).unwrap_err(),
+            LicenseKeyError::Version { version: 2 }
+        );
+
+        // Valid checksum, payload which is not a signature
+        let mut bytes = vec![LICENSE_KEY_VERSION, 0, 0, 0];
+        bytes.extend_from_slice(&checksum(&bytes));
+        assert_eq!(
+            Signature::<License, ()>::from_license_key(&encode_base32(&bytes)).unwrap_err(),
+            LicenseKeyError::Payload
+        );
+    }
+
+    #[test]
+    fn too_long() {
+        let signing_key: SigningKey<()> = signing_key();
+        let builder = SignatureBuilder::<_, ()>::new("toto mange du gateau".repeat(5));
+        let signature = signing_key.sign(builder).unwrap();
+
+        assert!(matches!(
+            signature.to_license_key().unwrap_err(),
+            LicenseKeyError::TooLong { .. }
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Signature, SignatureError};
+use base64ct::Encoding;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use snafu::{ResultExt, Snafu};
+
+/// Prefix of tokens, including the version of the format
+const TOKEN_PREFIX: &str = "sigserlic.v1.";
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+/// Errors when decoding a token produced by [`Signature::to_token()`]
+pub enum TokenError {
+    #[snafu(display("token does not start with `{TOKEN_PREFIX}`"))]
+    /// Not a token, or a token of an unsupported version
+    Prefix,
+    #[snafu(display("decoding base64"))]
+    /// Failed to decode base64url content of the token
+    Base64 {
+        /// Original error
+        source: base64ct::Error,
+    },
+    #[snafu(display("decoding signature"))]
+    /// Content of the token is not a valid signature
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+}
+
+impl<T: Serialize, C: Serialize> Signature<T, C> {
+    /// Encode the whole signature as a single URL-safe string, convenient to paste in a text field
+    ///
+    /// ```
+    /// # let signing_key = sigserlic::SigningKey::<()>::generate();
+    /// type MySignatureBuilder = sigserlic::SignatureBuilder::<String, ()>;
+    ///
+    /// let signature = signing_key.sign(MySignatureBuilder::new("toto".into())).unwrap();
+    /// let token = signature.to_token().unwrap();
+    /// assert!(token.starts_with("sigserlic.v1."));
+    /// ```
+    pub fn to_token(&self) -> Result<String, SignatureError> {
+        let bytes = self.to_compact_bytes()?;
+        Ok(format!(
+            "{TOKEN_PREFIX}{}",
+            base64ct::Base64UrlUnpadded::encode_string(&bytes)
+        ))
+    }
+}
+
+impl<T: DeserializeOwned, C: DeserializeOwned> Signature<T, C> {
+    /// Decode a signature from a token produced by [`Signature::to_token()`]
+    ///
+    /// The signature still needs to be verified with [`Signature::verify()`].
+    pub fn from_token(token: &str) -> Result<Self, TokenError> {
+        let content = token
+            .trim()
+            .strip_prefix(TOKEN_PREFIX)
+            .ok_or(TokenError::Prefix)?;
+        let bytes = base64ct::Base64UrlUnpadded::decode_vec(content).context(Base64Snafu)?;
+        Self::from_compact_bytes(&bytes).context(SignatureSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::DependencyError;
+    use crate::test_keys::{SIGNATURE_JSON, public_key};
+    use crate::{Message, PublicKey};
+    use jiff::Timestamp;
+
+    const TOKEN: &str = "sigserlic.v1.hnR0b3RvIG1hbmdlIGR1IGdhdGVhdYIaZVPxAACCGmtJ0gAA9lhKRWSXrjfqh35YjEVaXHKe_xHx9lB3zZc6uCqELgnqvHY6eeOB6ixhes_JR0VrYzu7FrBG2mdNtqjZt3I7ET9XoS2KTWtgzAnXWA9kdG90bw";
+
+    #[test]
+    fn encode() {
+        let signature: Signature<String, String> = serde_json::from_str(SIGNATURE_JSON).unwrap();
+        let token = signature.to_token().unwrap();
+        assert_eq!(token, TOKEN);
+        assert!(
+            token
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        );
+    }
+
+    #[test]
+    fn decode_and_verify() {
+        let signature: Signature<String, String> = Signature::from_token(TOKEN).unwrap();
+        let pubkey: PublicKey<()> = public_key();
+
+        assert_eq!(signature.comment(), Some(&"toto".into()));
+        assert_eq!(
+            signature.verify(&pubkey),
+            Ok(Message {
+                data: "toto mange du gateau".into(),
+                timestamp: Timestamp::from_second(1700000000).unwrap(),
+                expiration: Some(Timestamp::from_second(1800000000).unwrap()),
+                nonce: None,
+            })
+        );
+    }
+
+    #[test]
+    fn altered_token() {
+        let altered = TOKEN.replace("IGR1IG", "IGRhIG");
+        let signature: Signature<String, String> = Signature::from_token(&altered).unwrap();
+        let pubkey: PublicKey<()> = public_key();
+
+        assert_eq!(
+            signature.verify(&pubkey).unwrap_err(),
+            SignatureError::Verify {
+                source: DependencyError(libsignify::Error::BadSignature)
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_token() {
+        assert_eq!(
+            Signature::<String, String>::from_token(SIGNATURE_JSON).unwrap_err(),
+            TokenError::Prefix
+        );
+        assert_eq!(
+            Signature::<String, String>::from_token("sigserlic.v2.FHRvdG8").unwrap_err(),
+            TokenError::Prefix
+        );
+        assert!(matches!(
+            Signature::<String, String>::from_token("sigserlic.v1.toto+gateau").unwrap_err(),
+            TokenError::Base64 { .. }
+        ));
+        assert_eq!(
+            Signature::<String, String>::from_token("sigserlic.v1.FHRvdG8").unwrap_err(),
+            TokenError::Signature {
+                source: SignatureError::Cbor
+            }
+        );
+    }
+
+    #[test]
+    fn self_describing_data() {
+        let signature: Signature<serde_json::Value, String> =
+            serde_json::from_str(SIGNATURE_JSON).unwrap();
+        let token = signature.to_token().unwrap();
+
+        let decoded = Signature::<serde_json::Value, String>::from_token(&token).unwrap();
+        assert_eq!(decoded.comment(), Some(&"toto".into()));
+        let message = decoded.verify(&public_key()).unwrap();
+        assert_eq!(message.data(), "toto mange du gateau");
+    }
+}
@@ -1,17 +1,84 @@
 use crate::KeyMetadata;
 use crate::Metadata;
+#[cfg(feature = "generate")]
 use crate::error::TimestampError;
 
-#[derive(serde::Serialize, serde::Deserialize)]
 /// A key with the capability of signing data, producing a [`Signature`](crate::Signature), which can be
 /// verified by a [`PublicKey`](crate::PublicKey).
+///
+/// Serialized as a struct of `secret_key`, `created_at`, `expired_at` and `comment`. Every field
+/// is present in binary formats, so keys can be stored in formats which are not self-describing,
+/// like [`bincode`] or postcard:
+///
+/// ```
+/// # let signing_key = sigserlic::SigningKey::<String>::generate();
+/// let config = bincode::config::standard();
+/// let bytes = bincode::serde::encode_to_vec(&signing_key, config).unwrap();
+/// let (imported, _): (sigserlic::SigningKey<String>, _) =
+///     bincode::serde::decode_from_slice(&bytes, config).unwrap();
+/// ```
 pub struct SigningKey<C> {
-    #[serde(with = "signing_key_serde")]
     pub(crate) secret_key: libsignify::PrivateKey,
-    #[serde(flatten)]
     pub(crate) metadata: Metadata<C>,
 }
 
+/// Fields of a [`SigningKey`], with metadata inline instead of `#[serde(flatten)]`, given by name
+/// when `NAMED` (see [`fields_serde`](crate::fields_serde))
+#[derive(serde::Deserialize)]
+#[serde(rename = "SigningKey")]
+struct SigningKeyFields<C, const NAMED: bool> {
+    #[serde(deserialize_with = "signing_key_serde::deserialize::<_, NAMED>")]
+    secret_key: libsignify::PrivateKey,
+    #[serde(deserialize_with = "crate::timestamp::required::deserialize_field::<_, NAMED>")]
+    created_at: jiff::Timestamp,
+    #[serde(
+        default,
+        deserialize_with = "crate::timestamp::optional::deserialize_field::<_, NAMED>"
+    )]
+    expired_at: Option<jiff::Timestamp>,
+    comment: Option<C>,
+}
+
+/// Fields of a [`SigningKey`] in the order they are serialized
+const FIELDS: &[&str] = &["secret_key", "created_at", "expired_at", "comment"];
+
+impl<C, const NAMED: bool> From<SigningKeyFields<C, NAMED>> for SigningKey<C> {
+    fn from(fields: SigningKeyFields<C, NAMED>) -> Self {
+        Self {
+            secret_key: fields.secret_key,
+            metadata: Metadata {
+                created_at: fields.created_at,
+                expired_at: fields.expired_at,
+                comment: fields.comment,
+            },
+        }
+    }
+}
+
+impl<C: serde::Serialize> serde::Serialize for SigningKey<C> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let human_readable = serializer.is_human_readable();
+        let len = 1 + self.metadata.fields_len(human_readable);
+        let mut state = serializer.serialize_struct("SigningKey", len)?;
+        state.serialize_field("secret_key", &signing_key_serde::Field(&self.secret_key))?;
+        self.metadata.serialize_fields(&mut state, human_readable)?;
+        state.end()
+    }
+}
+
+impl<'de, C: serde::Deserialize<'de>> serde::Deserialize<'de> for SigningKey<C> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::fields_serde::deserialize::<
+            _,
+            SigningKeyFields<C, true>,
+            SigningKeyFields<C, false>,
+            _,
+        >(deserializer, "SigningKey", FIELDS)
+    }
+}
+
 impl<Comment: std::fmt::Debug> std::fmt::Debug for SigningKey<Comment> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SigningKey")
@@ -23,25 +90,33 @@ impl<Comment: std::fmt::Debug> std::fmt::Debug for SigningKey<Comment> {
 }
 
 mod signing_key_serde {
-    use base64ct::Encoding;
+    //! Base64 string in human-readable formats, raw bytes in binary formats
+
     use libsignify::{Codeable, PrivateKey};
-    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::{Deserializer, Serializer};
 
     pub fn serialize<S>(key: &PrivateKey, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let encoded = base64ct::Base64::encode_string(key.as_bytes().as_ref());
-        serializer.serialize_str(&encoded)
+        crate::base64_serde::native::serialize(key.as_bytes().as_ref(), serializer)
+    }
+
+    /// Key serialized with [`serialize()`], to be used as a field of a manual implementation
+    pub struct Field<'a>(pub &'a PrivateKey);
+
+    impl serde::Serialize for Field<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self.0, serializer)
+        }
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<PrivateKey, D::Error>
+    pub fn deserialize<'de, D, const NAMED: bool>(deserializer: D) -> Result<PrivateKey, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let key_in_base64: String = Deserialize::deserialize(deserializer)?;
         let key_in_bytes =
-            base64ct::Base64::decode_vec(&key_in_base64).map_err(serde::de::Error::custom)?;
+            crate::base64_serde::native::deserialize_field::<_, NAMED>(deserializer)?;
         PrivateKey::from_bytes(&key_in_bytes).map_err(serde::de::Error::custom)
     }
 }
@@ -134,6 +209,48 @@ impl<C> SigningKey<C> {
     {
         signature_builder.sign(self)
     }
+
+    /// Raw Ed25519 signature of `msg`, for formats other than signify
+    pub(crate) fn sign_raw(&self, msg: &[u8]) -> [u8; 64] {
+        self.secret_key.sign(msg).signature()
+    }
+
+    /// Raw Ed25519 key pair, secret key followed by public key, for formats other than signify
+    pub(crate) fn keypair_bytes(&self) -> [u8; 64] {
+        use libsignify::Codeable;
+
+        let bytes = self.secret_key.as_bytes();
+        bytes[bytes.len() - 64..]
+            .try_into()
+            .expect("complete key is at the end")
+    }
+
+    /// Build a key out of a raw Ed25519 key pair, secret key followed by public key, with a key
+    /// number derived from the public key unless given, and default metadata
+    pub(crate) fn from_keypair_bytes(
+        keypair: &[u8; 64],
+        keynum: Option<[u8; 8]>,
+    ) -> Result<Self, libsignify::Error> {
+        use libsignify::Codeable;
+        use sha2::{Digest, Sha512};
+
+        let public_key = keypair[32..].try_into().expect("32 bytes");
+        let keynum = keynum.unwrap_or_else(|| crate::key::derive_keynum(public_key));
+
+        // Layout of an unencrypted signify secret key
+        let mut bytes = Vec::with_capacity(104);
+        bytes.extend_from_slice(b"EdBK");
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(&Sha512::digest(keypair)[..8]);
+        bytes.extend_from_slice(&keynum);
+        bytes.extend_from_slice(keypair);
+
+        Ok(Self {
+            secret_key: libsignify::PrivateKey::from_bytes(&bytes)?,
+            metadata: Default::default(),
+        })
+    }
 }
 
 impl<C> KeyMetadata<C> for SigningKey<C> {
@@ -171,6 +288,69 @@ mod tests {
         assert!(format!("{key:?}").contains("<secret>"));
     }
 
+    /// Formats which are not self-describing, such as a binary keystore
+    mod not_self_describing {
+        use super::*;
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct MyStruct {
+            name: String,
+            age: u8,
+        }
+
+        const JSON: &str = r#"{"secret_key":"RWRCSwAAAAAqoN8nUn93E6gEYuiqdfJBYnt5X0f+VQ1cik6b4ImX143umMij6LwAkQfu/7VJkmbyEmMR1tW1LaH5ngNFQ/wEyaDyljmdcpUXX96KyiAUDacFzdWN3MNHEcuE83VyxWY=","created_at":"2024-12-22T23:21:47.572035Z","expired_at":"2025-06-15T15:06:40Z","comment":{"name":"Phil","age":24}}"#;
+
+        fn assert_same(key: &SigningKey<MyStruct>, expected: &SigningKey<MyStruct>) {
+            assert_eq!(key.keypair_bytes(), expected.keypair_bytes());
+            assert_eq!(key.keynum(), expected.keynum());
+            assert_eq!(key.metadata.created_at, expected.metadata.created_at);
+            assert_eq!(key.metadata.expired_at, expected.metadata.expired_at);
+            assert_eq!(key.metadata.comment, expected.metadata.comment);
+        }
+
+        #[test]
+        fn bincode() {
+            let mut key: SigningKey<MyStruct> = serde_json::from_str(JSON).unwrap();
+            for _ in 0..2 {
+                let bytes = bincode::serde::encode_to_vec(&key, crate::BINCODE_CONFIG).unwrap();
+                let (decoded, length): (SigningKey<MyStruct>, _) =
+                    bincode::serde::decode_from_slice(&bytes, crate::BINCODE_CONFIG).unwrap();
+                assert_eq!(length, bytes.len());
+                assert_same(&decoded, &key);
+
+                // Without optional fields
+                key.metadata.expired_at = None;
+                key.metadata.comment = None;
+            }
+        }
+
+        #[test]
+        fn postcard() {
+            let mut key: SigningKey<MyStruct> = serde_json::from_str(JSON).unwrap();
+            for _ in 0..2 {
+                let bytes = postcard::to_stdvec(&key).unwrap();
+                let decoded: SigningKey<MyStruct> = postcard::from_bytes(&bytes).unwrap();
+                assert_same(&decoded, &key);
+
+                key.metadata.expired_at = None;
+                key.metadata.comment = None;
+            }
+        }
+
+        #[test]
+        fn signing_after_roundtrip() {
+            let key: SigningKey<MyStruct> = serde_json::from_str(JSON).unwrap();
+            let bytes = bincode::serde::encode_to_vec(&key, crate::BINCODE_CONFIG).unwrap();
+            let (key, _): (SigningKey<MyStruct>, _) =
+                bincode::serde::decode_from_slice(&bytes, crate::BINCODE_CONFIG).unwrap();
+
+            let signature = crate::SignatureBuilder::<_, ()>::new("toto mange du gateau")
+                .sign(&key)
+                .unwrap();
+            assert!(signature.verify(&crate::PublicKey::from(key)).is_ok());
+        }
+    }
+
     #[cfg(feature = "generate")]
     mod generate {
         use super::*;
@@ -318,6 +498,26 @@ mod tests {
 
             #[test]
             fn cbor() {
+                let cbor: [u8; 161] = [
+                    164, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 88, 104, 69, 100, 66,
+                    75, 0, 0, 0, 0, 251, 57, 221, 38, 179, 218, 163, 43, 186, 67, 62, 45, 126, 211,
+                    186, 97, 144, 109, 204, 251, 107, 191, 180, 255, 151, 174, 55, 234, 135, 126,
+                    88, 140, 219, 39, 88, 99, 248, 20, 245, 226, 99, 157, 128, 139, 223, 86, 220,
+                    13, 20, 42, 187, 122, 230, 38, 125, 109, 136, 72, 156, 6, 113, 235, 112, 248,
+                    118, 138, 65, 216, 165, 6, 160, 178, 208, 45, 155, 67, 51, 36, 149, 120, 90,
+                    48, 241, 154, 127, 209, 127, 120, 235, 148, 35, 206, 139, 200, 176, 38, 106,
+                    99, 114, 101, 97, 116, 101, 100, 95, 97, 116, 130, 26, 103, 104, 171, 6, 26,
+                    32, 10, 14, 144, 106, 101, 120, 112, 105, 114, 101, 100, 95, 97, 116, 246, 103,
+                    99, 111, 109, 109, 101, 110, 116, 246,
+                ];
+                let key: SigningKey<String> = ciborium::from_reader(cbor.as_slice()).unwrap();
+                assert!(key.metadata.comment.is_none());
+                assert!(key.metadata.expired_at.is_none());
+            }
+
+            #[test]
+            fn cbor_0_2_0() {
+                // Written by 0.2.0, with strings for keys, signatures and timestamps
                 let cbor: [u8; 274] = [
                     191, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 120, 208, 52, 53, 54,
                     52, 52, 50, 52, 98, 48, 48, 48, 48, 48, 48, 48, 48, 102, 98, 51, 57, 100, 100,
@@ -355,6 +555,28 @@ mod tests {
 
             #[test]
             fn cbor() {
+                let cbor: [u8; 172] = [
+                    164, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 88, 104, 69, 100, 66,
+                    75, 0, 0, 0, 0, 42, 160, 223, 39, 82, 127, 119, 19, 168, 4, 98, 232, 170, 117,
+                    242, 65, 98, 123, 121, 95, 71, 254, 85, 13, 92, 138, 78, 155, 224, 137, 151,
+                    215, 141, 238, 152, 200, 163, 232, 188, 0, 145, 7, 238, 255, 181, 73, 146, 102,
+                    242, 18, 99, 17, 214, 213, 181, 45, 161, 249, 158, 3, 69, 67, 252, 4, 201, 160,
+                    242, 150, 57, 157, 114, 149, 23, 95, 222, 138, 202, 32, 20, 13, 167, 5, 205,
+                    213, 141, 220, 195, 71, 17, 203, 132, 243, 117, 114, 197, 102, 106, 99, 114,
+                    101, 97, 116, 101, 100, 95, 97, 116, 130, 26, 103, 104, 159, 11, 26, 34, 24,
+                    143, 184, 106, 101, 120, 112, 105, 114, 101, 100, 95, 97, 116, 246, 103, 99,
+                    111, 109, 109, 101, 110, 116, 107, 116, 101, 115, 116, 105, 110, 103, 32, 107,
+                    101, 121,
+                ];
+
+                let key: SigningKey<String> = ciborium::from_reader(cbor.as_slice()).unwrap();
+                assert_eq!(key.metadata.comment, Some("testing key".into()));
+                assert!(key.metadata.expired_at.is_none());
+            }
+
+            #[test]
+            fn cbor_0_2_0() {
+                // Written by 0.2.0, with strings for keys, signatures and timestamps
                 let cbor: [u8; 295] = [
                     191, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 120, 208, 52, 53, 54,
                     52, 52, 50, 52, 98, 48, 48, 48, 48, 48, 48, 48, 48, 50, 97, 97, 48, 100, 102,
@@ -412,6 +634,39 @@ mod tests {
 
             #[test]
             fn cbor() {
+                let cbor: [u8; 192] = [
+                    164, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 88, 104, 69, 100, 66,
+                    75, 0, 0, 0, 0, 196, 126, 231, 186, 139, 226, 206, 243, 117, 149, 162, 50, 198,
+                    95, 7, 41, 145, 124, 183, 181, 75, 109, 216, 179, 131, 30, 31, 236, 226, 201,
+                    129, 80, 146, 8, 232, 151, 214, 186, 97, 165, 230, 213, 9, 140, 9, 238, 103,
+                    212, 0, 36, 9, 232, 13, 215, 100, 229, 234, 58, 200, 12, 67, 201, 128, 242,
+                    233, 43, 21, 172, 103, 196, 44, 135, 55, 116, 253, 179, 71, 93, 62, 88, 130,
+                    61, 126, 82, 194, 4, 101, 217, 174, 42, 185, 32, 121, 46, 219, 238, 106, 99,
+                    114, 101, 97, 116, 101, 100, 95, 97, 116, 130, 26, 103, 104, 174, 143, 26, 17,
+                    185, 31, 232, 106, 101, 120, 112, 105, 114, 101, 100, 95, 97, 116, 130, 26,
+                    104, 78, 225, 128, 0, 103, 99, 111, 109, 109, 101, 110, 116, 163, 100, 110, 97,
+                    109, 101, 100, 80, 104, 105, 108, 99, 97, 103, 101, 24, 24, 103, 97, 119, 101,
+                    115, 111, 109, 101, 245,
+                ];
+
+                let key: SigningKey<MyStruct> = ciborium::from_reader(cbor.as_slice()).unwrap();
+                assert_eq!(
+                    key.metadata.comment,
+                    Some(MyStruct {
+                        name: "Phil".into(),
+                        age: 24,
+                        awesome: true
+                    })
+                );
+                assert_eq!(
+                    key.metadata.expired_at.map(|e| e.as_second()),
+                    Some(1750000000)
+                );
+            }
+
+            #[test]
+            fn cbor_0_2_0() {
+                // Written by 0.2.0, with strings for keys, signatures and timestamps
                 let cbor: [u8; 329] = [
                     191, 106, 115, 101, 99, 114, 101, 116, 95, 107, 101, 121, 120, 208, 52, 53, 54,
                     52, 52, 50, 52, 98, 48, 48, 48, 48, 48, 48, 48, 48, 99, 52, 55, 101, 101, 55,
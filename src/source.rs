@@ -0,0 +1,23 @@
+//! Errors of dependencies, kept as `source` of errors of the crate
+//!
+//! Some dependencies implement [`core::error::Error`] only with their `std` feature. Their errors
+//! are wrapped in a [`DependencyError`], with or without `std` for errors to be the same type in
+//! both builds.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Error of a dependency which implements [`core::error::Error`] only with the standard library
+pub struct DependencyError<E>(pub E);
+
+impl<E: core::fmt::Display> core::fmt::Display for DependencyError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<E: core::fmt::Debug + core::fmt::Display> core::error::Error for DependencyError<E> {}
+
+impl<E> From<E> for DependencyError<E> {
+    fn from(error: E) -> Self {
+        Self(error)
+    }
+}
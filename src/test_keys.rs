@@ -0,0 +1,72 @@
+//! Keys and signatures shared by the tests of every module
+
+// Most tests sign, without it they are compiled out along with the signing code
+#![cfg_attr(not(feature = "sign"), allow(dead_code))]
+
+use crate::PublicKey;
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+#[cfg(feature = "sign")]
+use serde::Serialize;
+#[cfg(feature = "sign")]
+use serde::de::DeserializeOwned;
+
+/// Signing key, without comment
+pub const SIGNING_KEY_JSON: &str = r#"{"secret_key":"RWRCSwAAAAD7Od0ms9qjK7pDPi1+07phkG3M+2u/tP+Xrjfqh35YjNsnWGP4FPXiY52Ai99W3A0UKrt65iZ9bYhInAZx63D4dopB2KUGoLLQLZtDMySVeFow8Zp/0X9465QjzovIsCY=","created_at":"2024-12-23T00:12:54.53753Z","expired_at":null}"#;
+
+/// Public key of [`SIGNING_KEY_JSON`]
+pub const PUBLIC_KEY_JSON: &str = r#"{"public_key":"RWSXrjfqh35YjHaKQdilBqCy0C2bQzMklXhaMPGaf9F/eOuUI86LyLAm","created_at":"2024-12-23T00:12:54.53753Z","expired_at":null}"#;
+
+/// `"toto mange du gateau"` signed by [`SIGNING_KEY_JSON`], expiring in 2027, with a comment
+pub const SIGNATURE_JSON: &str = r#"{"signed_artifact":{"data":"toto mange du gateau","timestamp":"2023-11-14T22:13:20Z","expiration":"2027-01-15T08:00:00Z"},"signature":"RWSXrjfqh35YjEVaXHKe/xHx9lB3zZc6uCqELgnqvHY6eeOB6ixhes/JR0VrYzu7FrBG2mdNtqjZt3I7ET9XoS2KTWtgzAnXWA8=","comment":"toto"}"#;
+
+/// Another signing key, without comment
+pub const OTHER_SIGNING_KEY_JSON: &str = r#"{"secret_key":"RWRCSwAAAAAqoN8nUn93E6gEYuiqdfJBYnt5X0f+VQ1cik6b4ImX143umMij6LwAkQfu/7VJkmbyEmMR1tW1LaH5ngNFQ/wEyaDyljmdcpUXX96KyiAUDacFzdWN3MNHEcuE83VyxWY=","created_at":"2024-12-22T23:21:47.572035Z","expired_at":null}"#;
+
+/// [`OTHER_SIGNING_KEY_JSON`] with the comment `testing key`
+pub const COMMENTED_SIGNING_KEY_JSON: &str = r#"{"secret_key":"RWRCSwAAAAAqoN8nUn93E6gEYuiqdfJBYnt5X0f+VQ1cik6b4ImX143umMij6LwAkQfu/7VJkmbyEmMR1tW1LaH5ngNFQ/wEyaDyljmdcpUXX96KyiAUDacFzdWN3MNHEcuE83VyxWY=","created_at":"2024-12-22T23:21:47.572035Z","expired_at":null,"comment":"testing key"}"#;
+
+/// Public key of a third key pair, made by neither [`SIGNING_KEY_JSON`] nor
+/// [`OTHER_SIGNING_KEY_JSON`]
+pub const OTHER_PUBLIC_KEY_JSON: &str = r#"{"public_key":"RWQnJUuDaiWf2BAemrs2IhCFqOIW6Ivotz6JpSAq4ch55WC/rz/fq0mY","created_at":"2024-12-23T16:39:25.85933Z","expired_at":null}"#;
+
+/// [`SIGNING_KEY_JSON`]
+#[cfg(feature = "sign")]
+pub fn signing_key() -> SigningKey<()> {
+    serde_json::from_str(SIGNING_KEY_JSON).unwrap()
+}
+
+/// [`PUBLIC_KEY_JSON`]
+pub fn public_key() -> PublicKey<()> {
+    serde_json::from_str(PUBLIC_KEY_JSON).unwrap()
+}
+
+/// [`OTHER_PUBLIC_KEY_JSON`]
+pub fn other_public_key() -> PublicKey<()> {
+    serde_json::from_str(OTHER_PUBLIC_KEY_JSON).unwrap()
+}
+
+/// [`OTHER_SIGNING_KEY_JSON`]
+#[cfg(feature = "sign")]
+pub fn other_signing_key() -> SigningKey<()> {
+    serde_json::from_str(OTHER_SIGNING_KEY_JSON).unwrap()
+}
+
+/// [`OTHER_SIGNING_KEY_JSON`] and its public key, with any type of comment since it has none
+#[cfg(feature = "sign")]
+pub fn other_key_pair<C: DeserializeOwned>() -> (SigningKey<C>, PublicKey<C>) {
+    let parse = || serde_json::from_str::<SigningKey<C>>(OTHER_SIGNING_KEY_JSON).unwrap();
+    (parse(), PublicKey::from(parse()))
+}
+
+/// [`OTHER_SIGNING_KEY_JSON`] with its expiration and comment replaced
+#[cfg(feature = "sign")]
+pub fn other_signing_key_with<C: Serialize + DeserializeOwned>(
+    expired_at: Option<&str>,
+    comment: Option<C>,
+) -> SigningKey<C> {
+    let mut json: serde_json::Value = serde_json::from_str(OTHER_SIGNING_KEY_JSON).unwrap();
+    json["expired_at"] = serde_json::json!(expired_at);
+    json["comment"] = serde_json::json!(comment);
+    serde_json::from_value(json).unwrap()
+}
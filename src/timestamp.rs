@@ -1,7 +1,11 @@
+//! Timestamps are ISO 8601 strings in human-readable formats, and integers in binary formats, see
+//! [`compact`].
+
+use alloc::string::{String, ToString};
+use core::str::FromStr;
 use jiff::Timestamp;
 use serde::{Deserialize, Deserializer, Serializer};
-use snafu::{ResultExt, Snafu};
-use std::str::FromStr;
+use snafu::Snafu;
 
 pub(crate) mod required {
     use super::*;
@@ -10,6 +14,9 @@ pub(crate) mod required {
     where
         S: Serializer,
     {
+        if !serializer.is_human_readable() {
+            return compact::required::serialize(timestamp, serializer);
+        }
         serializer.collect_str(timestamp)
     }
 
@@ -17,10 +24,28 @@ pub(crate) mod required {
     where
         D: Deserializer<'de>,
     {
+        if !deserializer.is_human_readable() {
+            return compact::required::deserialize(deserializer);
+        }
+
         // Make sure value is an owned string, some serde implementations will fail on a slice (ex: `ciborium`)
         let string = String::deserialize(deserializer)?;
         Timestamp::from_str(&string).map_err(serde::de::Error::custom)
     }
+
+    /// [`deserialize()`] for a field of a struct, which binary formats may hold as a string
+    /// written up to 0.2.0 when `NAMED` (see [`fields_serde`](crate::fields_serde))
+    pub fn deserialize_field<'de, D, const NAMED: bool>(
+        deserializer: D,
+    ) -> Result<Timestamp, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if NAMED && !deserializer.is_human_readable() {
+            return deserializer.deserialize_any(compact::TimestampVisitor);
+        }
+        deserialize(deserializer)
+    }
 }
 
 pub(crate) mod optional {
@@ -30,6 +55,9 @@ pub(crate) mod optional {
     where
         S: Serializer,
     {
+        if !serializer.is_human_readable() {
+            return compact::optional::serialize(optional, serializer);
+        }
         match optional {
             Some(value) => serializer.serialize_some(&value.to_string()),
             None => serializer.serialize_none(),
@@ -40,6 +68,10 @@ pub(crate) mod optional {
     where
         D: Deserializer<'de>,
     {
+        if !deserializer.is_human_readable() {
+            return compact::optional::deserialize(deserializer);
+        }
+
         // Make sure value is an owned string, some serde implementations will fail on a slice (ex: `ciborium`)
         let string = Option::<String>::deserialize(deserializer)?;
 
@@ -50,6 +82,143 @@ pub(crate) mod optional {
 
         Ok(timestamp)
     }
+
+    /// [`deserialize()`] for a field of a struct, like [`required::deserialize_field()`]
+    pub fn deserialize_field<'de, D, const NAMED: bool>(
+        deserializer: D,
+    ) -> Result<Option<Timestamp>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if NAMED && !deserializer.is_human_readable() {
+            return deserializer.deserialize_option(compact::OptionalVisitor::<true>);
+        }
+        deserialize(deserializer)
+    }
+}
+
+/// Timestamp serialized like [`required`], for manual [`Serialize`](serde::Serialize) implementations
+pub(crate) struct Required(pub(crate) Timestamp);
+
+impl serde::Serialize for Required {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        required::serialize(&self.0, serializer)
+    }
+}
+
+/// Timestamp serialized like [`optional`], for manual [`Serialize`](serde::Serialize) implementations
+pub(crate) struct Optional(pub(crate) Option<Timestamp>);
+
+impl serde::Serialize for Optional {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        optional::serialize(&self.0, serializer)
+    }
+}
+
+/// Timestamps as a pair of seconds and nanoseconds, for binary formats
+///
+/// ISO 8601 strings, written by binary formats up to 0.2.0, are accepted as well by formats
+/// describing their values.
+pub(crate) mod compact {
+    use super::*;
+    use serde::Serialize;
+    use serde::de::{SeqAccess, Visitor};
+
+    pub(crate) mod required {
+        use super::*;
+
+        pub fn serialize<S>(timestamp: &Timestamp, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            (timestamp.as_second(), timestamp.subsec_nanosecond()).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Timestamp, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_tuple(2, TimestampVisitor)
+        }
+    }
+
+    pub(crate) mod optional {
+        use super::*;
+
+        pub fn serialize<S>(optional: &Option<Timestamp>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            optional
+                .map(|timestamp| (timestamp.as_second(), timestamp.subsec_nanosecond()))
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Timestamp>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_option(OptionalVisitor::<false>)
+        }
+    }
+
+    /// Pair of seconds and nanoseconds, or ISO 8601 string
+    pub(crate) struct TimestampVisitor;
+
+    impl<'de> Visitor<'de> for TimestampVisitor {
+        type Value = Timestamp;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("seconds and nanoseconds, or an ISO 8601 timestamp")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            use serde::de::Error;
+
+            let second: i64 = seq
+                .next_element()?
+                .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+            let nanosecond: i32 = seq
+                .next_element()?
+                .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+            Timestamp::new(second, nanosecond).map_err(A::Error::custom)
+        }
+
+        fn visit_str<E: serde::de::Error>(self, string: &str) -> Result<Self::Value, E> {
+            Timestamp::from_str(string).map_err(E::custom)
+        }
+    }
+
+    /// Optional [`TimestampVisitor`], reading any value the format describes when `NAMED`
+    pub(crate) struct OptionalVisitor<const NAMED: bool>;
+
+    impl<'de, const NAMED: bool> Visitor<'de> for OptionalVisitor<NAMED> {
+        type Value = Option<Timestamp>;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("an optional timestamp")
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        // Null of MessagePack
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            if NAMED {
+                deserializer.deserialize_any(TimestampVisitor).map(Some)
+            } else {
+                required::deserialize(deserializer).map(Some)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -57,10 +226,14 @@ pub(crate) mod optional {
 /// Error while parsing a timestamp from an integer
 pub struct TimestampError {
     timestamp: i64,
-    source: jiff::Error,
+    #[snafu(source(from(jiff::Error, Into::into)))]
+    source: crate::source::DependencyError<jiff::Error>,
 }
 
+#[cfg(feature = "std")]
 pub(crate) fn parse_timestamp(timestamp: i64) -> Result<Timestamp, TimestampError> {
+    use snafu::ResultExt;
+
     Timestamp::from_second(timestamp).context(TimestampSnafu { timestamp })
 }
 
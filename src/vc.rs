@@ -0,0 +1,484 @@
+//! Issue signatures as [W3C Verifiable Credentials](https://www.w3.org/TR/vc-data-model-2.0/),
+//! secured with a Data Integrity proof using the
+//! [`eddsa-jcs-2022`](https://www.w3.org/TR/vc-di-eddsa/#eddsa-jcs-2022) cryptosuite
+//!
+//! The data becomes the `credentialSubject`, the [`Message`] timestamp becomes `validFrom` and the
+//! proof `created` date, its expiration becomes `validUntil`, and its nonce the proof `nonce`. The
+//! issuer is the `did:key` of the key pair, see [`crate::did`].
+//!
+//! The proof is an Ed25519 signature of the SHA-256 hashes of the proof options and of the
+//! credential, both canonicalized with JCS ([RFC 8785](https://www.rfc-editor.org/rfc/rfc8785)).
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Certification {
+//!     name: String,
+//!     level: u8,
+//! }
+//!
+//! let builder = sigserlic::SignatureBuilder::<_, ()>::new(Certification {
+//!     name: "Toto".into(),
+//!     level: 3,
+//! });
+//! let signature = signing_key.sign(builder).unwrap();
+//!
+//! let credential = signature.to_credential(&signing_key).unwrap();
+//!
+//! // Anyone holding the public key of the issuer checks the proof
+//! let public_key = sigserlic::PublicKey::from(signing_key);
+//! let message = public_key
+//!     .verify_credential::<Certification>(&credential)
+//!     .unwrap();
+//! assert_eq!(message.data().level, 3);
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::KeyMetadata;
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::did::{BASE58BTC, DidError};
+use crate::error::SignatureError;
+use crate::{Message, Nonce, PublicKey, Signature};
+use jiff::Timestamp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+
+/// Base context of the Verifiable Credentials Data Model v2.0
+const CONTEXT_V2: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// Type every credential has
+const CREDENTIAL_TYPE: &str = "VerifiableCredential";
+
+/// Type of Data Integrity proofs
+const PROOF_TYPE: &str = "DataIntegrityProof";
+
+/// Cryptosuite of Ed25519 signatures over JCS canonicalized documents
+const CRYPTOSUITE: &str = "eddsa-jcs-2022";
+
+/// Purpose of proofs made by issuers
+const PROOF_PURPOSE: &str = "assertionMethod";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Credential<T> {
+    #[serde(rename = "@context")]
+    context: Value,
+    #[serde(rename = "type")]
+    types: Vec<String>,
+    issuer: String,
+    #[serde(with = "crate::timestamp::required")]
+    valid_from: Timestamp,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::timestamp::optional"
+    )]
+    valid_until: Option<Timestamp>,
+    credential_subject: T,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Proof {
+    #[serde(rename = "@context", default, skip_serializing_if = "Option::is_none")]
+    context: Option<Value>,
+    #[serde(rename = "type")]
+    proof_type: String,
+    cryptosuite: String,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::timestamp::optional"
+    )]
+    created: Option<Timestamp>,
+    verification_method: String,
+    proof_purpose: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce: Option<Nonce>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proof_value: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when issuing or verifying a Verifiable Credential
+pub enum CredentialError {
+    #[snafu(display("credential or proof"))]
+    /// Failed to encode or decode the credential or its proof with json
+    Json {
+        /// Original error
+        source: serde_json::Error,
+    },
+    #[snafu(display("credential subject is not a map"))]
+    /// The data does not serialize as a map
+    Subject,
+    #[snafu(display("not a verifiable credential"))]
+    /// The document is not a map, misses the v2 context or the `VerifiableCredential` type
+    Format,
+    #[snafu(display("credential has no proof"))]
+    /// The credential has no `proof`, or more than one
+    MissingProof,
+    #[snafu(display("unsupported proof `{cryptosuite}`"))]
+    /// The proof is not a `DataIntegrityProof` using `eddsa-jcs-2022`
+    Cryptosuite {
+        /// Cryptosuite of the proof
+        cryptosuite: String,
+    },
+    #[snafu(display("proof purpose `{purpose}` is not `assertionMethod`"))]
+    /// The proof was not made by the issuer of the credential
+    ProofPurpose {
+        /// Purpose of the proof
+        purpose: String,
+    },
+    #[snafu(display("context of proof does not match the credential"))]
+    /// The `@context` of the proof is not the start of the one of the credential
+    Context,
+    #[snafu(display("verification method of proof"))]
+    /// The verification method of the proof is not a `did:key`
+    VerificationMethod {
+        /// Original error
+        source: DidError,
+    },
+    #[snafu(display("credential was issued by another key"))]
+    /// The issuer or verification method is not the key
+    KeyMismatch,
+    #[snafu(display("decoding proof value"))]
+    /// The proof value is not a signature encoded in multibase base58btc
+    ProofValue,
+    #[snafu(display("decoding signature"))]
+    /// The signature to convert could not be decoded
+    Signature {
+        /// Original error
+        source: SignatureError,
+    },
+    #[snafu(display("verify signature with public key"))]
+    /// Failed to use key to verify authenticity of credential
+    Verify {
+        /// Original error
+        source: libsignify::Error,
+    },
+}
+
+/// Data signed by `eddsa-jcs-2022`: hash of the proof options followed by hash of the document
+fn hash_data(proof_config: &Value, document: &Value) -> Vec<u8> {
+    let proof_config = Sha256::digest(crate::jcs::canonicalize(proof_config));
+    let document = Sha256::digest(crate::jcs::canonicalize(document));
+    [proof_config, document].concat()
+}
+
+impl<T: Serialize, C> Signature<T, C> {
+    #[cfg(feature = "sign")]
+    /// Issue the signed message as a Verifiable Credential, with a proof made by `signing_key`
+    ///
+    /// The issuer of the credential is the signer of the message, a proof by another key is
+    /// refused with [`CredentialError::KeyMismatch`]. The data must serialize as a map, it becomes
+    /// the subject of the credential. The comment is not part of the credential.
+    pub fn to_credential<S>(&self, signing_key: &SigningKey<S>) -> Result<String, CredentialError> {
+        let signer = self.signature().context(SignatureSnafu)?.signer_keynum();
+        if signer != signing_key.keynum() {
+            return Err(CredentialError::KeyMismatch);
+        }
+
+        let public_key =
+            PublicKey::<()>::from_key_bytes(&signing_key.secret_key.public().key(), None);
+        let issuer = public_key.to_did_key();
+        let message = &self.signed_artifact;
+
+        let credential = Credential {
+            context: Value::from([CONTEXT_V2]),
+            types: vec![CREDENTIAL_TYPE.into()],
+            issuer: issuer.clone(),
+            valid_from: message.timestamp,
+            valid_until: message.expiration,
+            credential_subject: &message.data,
+        };
+        let mut document = serde_json::to_value(&credential).context(JsonSnafu)?;
+        if !document["credentialSubject"].is_object() {
+            return Err(CredentialError::Subject);
+        }
+
+        let mut proof = Proof {
+            context: Some(credential.context),
+            proof_type: PROOF_TYPE.into(),
+            cryptosuite: CRYPTOSUITE.into(),
+            created: Some(message.timestamp),
+            verification_method: format!("{issuer}#{}", public_key.to_multibase()),
+            proof_purpose: PROOF_PURPOSE.into(),
+            nonce: message.nonce,
+            proof_value: None,
+        };
+        let proof_config = serde_json::to_value(&proof).context(JsonSnafu)?;
+
+        let signature = signing_key.sign_raw(&hash_data(&proof_config, &document));
+        proof.proof_value = Some(format!(
+            "{BASE58BTC}{}",
+            bs58::encode(signature).into_string()
+        ));
+        document["proof"] = serde_json::to_value(&proof).context(JsonSnafu)?;
+
+        serde_json::to_string(&document).context(JsonSnafu)
+    }
+}
+
+impl<C> PublicKey<C> {
+    /// Verify a Verifiable Credential issued by the signing key of this public key, and get the
+    /// message out of its subject and validity dates
+    ///
+    /// Validity dates are carried over to the message without being compared to the current
+    /// time, which is the job of [`Message::is_expired_at()`].
+    pub fn verify_credential<T: DeserializeOwned>(
+        &self,
+        credential: &str,
+    ) -> Result<Message<T>, CredentialError> {
+        let mut document: Value = serde_json::from_str(credential).context(JsonSnafu)?;
+        let mut proof_config = document
+            .as_object_mut()
+            .ok_or(CredentialError::Format)?
+            .remove("proof")
+            .filter(Value::is_object)
+            .ok_or(CredentialError::MissingProof)?;
+        let proof: Proof = serde_json::from_value(proof_config.clone()).context(JsonSnafu)?;
+        if let Some(proof_config) = proof_config.as_object_mut() {
+            proof_config.remove("proofValue");
+        }
+
+        if proof.proof_type != PROOF_TYPE || proof.cryptosuite != CRYPTOSUITE {
+            return Err(CredentialError::Cryptosuite {
+                cryptosuite: proof.cryptosuite,
+            });
+        }
+        if proof.proof_purpose != PROOF_PURPOSE {
+            return Err(CredentialError::ProofPurpose {
+                purpose: proof.proof_purpose,
+            });
+        }
+        let context = document["@context"]
+            .as_array()
+            .ok_or(CredentialError::Format)?;
+        if let Some(proof_context) = &proof.context {
+            let proof_context = proof_context.as_array().ok_or(CredentialError::Context)?;
+            if !context.starts_with(proof_context) {
+                return Err(CredentialError::Context);
+            }
+        }
+        if context.first().and_then(Value::as_str) != Some(CONTEXT_V2) {
+            return Err(CredentialError::Format);
+        }
+
+        let key = crate::did::parse_did_key(&proof.verification_method)
+            .context(VerificationMethodSnafu)?;
+        if key != self.key_bytes() {
+            return Err(CredentialError::KeyMismatch);
+        }
+
+        let signature = proof
+            .proof_value
+            .as_deref()
+            .and_then(|value| value.strip_prefix(BASE58BTC))
+            .and_then(|base58| bs58::decode(base58).into_vec().ok())
+            .filter(|signature| signature.len() == 64)
+            .ok_or(CredentialError::ProofValue)?;
+        self.verify_raw(&hash_data(&proof_config, &document), &signature)
+            .context(VerifySnafu)?;
+
+        let credential: Credential<T> = serde_json::from_value(document).context(JsonSnafu)?;
+        if !credential.types.iter().any(|t| t == CREDENTIAL_TYPE) {
+            return Err(CredentialError::Format);
+        }
+        let (verification_did, _) = proof
+            .verification_method
+            .split_once('#')
+            .unwrap_or((&proof.verification_method, ""));
+        if credential.issuer != verification_did {
+            return Err(CredentialError::KeyMismatch);
+        }
+
+        Ok(Message {
+            data: credential.credential_subject,
+            timestamp: credential.valid_from,
+            expiration: credential.valid_until,
+            nonce: proof.nonce,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::SignatureBuilder;
+    use crate::test_keys::{other_public_key, other_signing_key, public_key, signing_key};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Certification {
+        name: String,
+        level: u8,
+    }
+
+    fn signature() -> Signature<Certification, ()> {
+        let builder = SignatureBuilder::new(Certification {
+            name: "Toto".into(),
+            level: 3,
+        })
+        .timestamp(1700000000)
+        .unwrap()
+        .expiration(1800000000)
+        .unwrap()
+        .nonce(Nonce::from([1; Nonce::LEN]));
+        signing_key().sign(builder).unwrap()
+    }
+
+    fn credential() -> Value {
+        let credential = signature().to_credential(&signing_key()).unwrap();
+        serde_json::from_str(&credential).unwrap()
+    }
+
+    #[test]
+    fn export() {
+        let mut credential = credential();
+        let did = public_key().to_did_key();
+        let proof_value = credential["proof"]
+            .as_object_mut()
+            .unwrap()
+            .remove("proofValue")
+            .unwrap();
+        assert!(proof_value.as_str().unwrap().starts_with('z'));
+
+        let multibase = did.strip_prefix("did:key:").unwrap();
+        assert_eq!(
+            credential,
+            serde_json::json!({
+                "@context": ["https://www.w3.org/ns/credentials/v2"],
+                "type": ["VerifiableCredential"],
+                "issuer": did,
+                "validFrom": "2023-11-14T22:13:20Z",
+                "validUntil": "2027-01-15T08:00:00Z",
+                "credentialSubject": {"name": "Toto", "level": 3},
+                "proof": {
+                    "@context": ["https://www.w3.org/ns/credentials/v2"],
+                    "type": "DataIntegrityProof",
+                    "cryptosuite": "eddsa-jcs-2022",
+                    "created": "2023-11-14T22:13:20Z",
+                    "verificationMethod": format!("{did}#{multibase}"),
+                    "proofPurpose": "assertionMethod",
+                    "nonce": "AQEBAQEBAQEBAQEBAQEBAQ==",
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let credential = signature().to_credential(&signing_key()).unwrap();
+        let message = public_key()
+            .verify_credential::<Certification>(&credential)
+            .unwrap();
+
+        assert_eq!(message, signature().verify(&signing_key().into()).unwrap());
+    }
+
+    #[test]
+    fn formatting_does_not_matter() {
+        let credential = serde_json::to_string_pretty(&credential()).unwrap();
+        assert!(
+            public_key()
+                .verify_credential::<Certification>(&credential)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn altered() {
+        let public_key = public_key();
+
+        let mut credential = credential();
+        credential["credentialSubject"]["level"] = 5.into();
+        assert!(matches!(
+            public_key
+                .verify_credential::<Certification>(&credential.to_string())
+                .unwrap_err(),
+            CredentialError::Verify { .. }
+        ));
+
+        let mut credential = self::credential();
+        credential["validUntil"] = "2099-01-01T00:00:00Z".into();
+        assert!(matches!(
+            public_key
+                .verify_credential::<Certification>(&credential.to_string())
+                .unwrap_err(),
+            CredentialError::Verify { .. }
+        ));
+
+        let mut credential = self::credential();
+        credential["proof"]["created"] = "2020-01-01T00:00:00Z".into();
+        assert!(matches!(
+            public_key
+                .verify_credential::<Certification>(&credential.to_string())
+                .unwrap_err(),
+            CredentialError::Verify { .. }
+        ));
+    }
+
+    #[test]
+    fn wrong_key() {
+        let credential = signature().to_credential(&signing_key()).unwrap();
+        let other = other_public_key();
+        assert!(matches!(
+            other
+                .verify_credential::<Certification>(&credential)
+                .unwrap_err(),
+            CredentialError::KeyMismatch
+        ));
+
+        // Issuing requires the key which made the signature
+        let other: SigningKey<()> = other_signing_key();
+        assert!(matches!(
+            signature().to_credential(&other).unwrap_err(),
+            CredentialError::KeyMismatch
+        ));
+    }
+
+    #[test]
+    fn invalid_proof() {
+        let public_key = public_key();
+
+        let mut credential = credential();
+        credential["proof"]["cryptosuite"] = "eddsa-rdfc-2022".into();
+        assert!(matches!(
+            public_key
+                .verify_credential::<Certification>(&credential.to_string())
+                .unwrap_err(),
+            CredentialError::Cryptosuite { cryptosuite } if cryptosuite == "eddsa-rdfc-2022"
+        ));
+
+        let mut credential = self::credential();
+        credential.as_object_mut().unwrap().remove("proof");
+        assert!(matches!(
+            public_key
+                .verify_credential::<Certification>(&credential.to_string())
+                .unwrap_err(),
+            CredentialError::MissingProof
+        ));
+
+        let mut credential = self::credential();
+        credential["proof"]["proofValue"] = "uAAAA".into();
+        assert!(matches!(
+            public_key
+                .verify_credential::<Certification>(&credential.to_string())
+                .unwrap_err(),
+            CredentialError::ProofValue
+        ));
+    }
+
+    #[test]
+    fn subject_must_be_a_map() {
+        let builder = SignatureBuilder::<_, ()>::new(String::from("toto"));
+        let signature = signing_key().sign(builder).unwrap();
+        assert!(matches!(
+            signature.to_credential(&signing_key()).unwrap_err(),
+            CredentialError::Subject
+        ));
+    }
+}
@@ -0,0 +1,479 @@
+//! Self-signed X.509 certificates of Ed25519 keys ([RFC 8410](https://www.rfc-editor.org/rfc/rfc8410)),
+//! for tools which want a certificate rather than a bare key, such as mTLS or code signing
+//!
+//! The certificate is valid from the creation of the key until its expiration, or forever
+//! (`99991231235959Z`) if the key does not expire, truncated to seconds. It is not a CA, and may
+//! only be used for digital signatures.
+//!
+//! The key number travels in a non-critical extension, ignored by other tools: the public key
+//! extracted from a certificate made by sigserlic verifies signatures of the signing key.
+//! Certificates made by other tools get a key number derived from the public key.
+//!
+//! ```
+//! # let signing_key = sigserlic::SigningKey::<()>::generate();
+//! use sigserlic::x509::CertificateParams;
+//!
+//! let params = CertificateParams::new("toto").dns_name("localhost");
+//! let pem = signing_key.to_x509_pem(&params);
+//! assert!(pem.starts_with("-----BEGIN CERTIFICATE-----\n"));
+//!
+//! let public_key = sigserlic::PublicKey::<()>::from_x509_pem(&pem).unwrap();
+//! ```
+
+#[cfg(feature = "sign")]
+use crate::KeyMetadata;
+use crate::PublicKey;
+#[cfg(feature = "sign")]
+use crate::SigningKey;
+use crate::der::{self, Reader};
+use jiff::Timestamp;
+use jiff::tz::TimeZone;
+#[cfg(feature = "sign")]
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+#[cfg(feature = "sign")]
+use std::net::IpAddr;
+
+/// Label of certificates in PEM
+const CERTIFICATE_LABEL: &str = "CERTIFICATE";
+
+const BOOLEAN: u8 = 0x01;
+#[cfg(feature = "sign")]
+const UTF8_STRING: u8 = 0x0c;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+
+#[cfg(feature = "sign")]
+/// Encoded object identifier of the common name attribute, 2.5.4.3
+const COMMON_NAME_OID: &[u8] = &[0x55, 0x04, 0x03];
+#[cfg(feature = "sign")]
+/// Encoded object identifier of the subject key identifier extension, 2.5.29.14
+const SUBJECT_KEY_IDENTIFIER_OID: &[u8] = &[0x55, 0x1d, 0x0e];
+#[cfg(feature = "sign")]
+/// Encoded object identifier of the key usage extension, 2.5.29.15
+const KEY_USAGE_OID: &[u8] = &[0x55, 0x1d, 0x0f];
+#[cfg(feature = "sign")]
+/// Encoded object identifier of the subject alternative name extension, 2.5.29.17
+const SUBJECT_ALT_NAME_OID: &[u8] = &[0x55, 0x1d, 0x11];
+#[cfg(feature = "sign")]
+/// Encoded object identifier of the basic constraints extension, 2.5.29.19
+const BASIC_CONSTRAINTS_OID: &[u8] = &[0x55, 0x1d, 0x13];
+
+/// `notAfter` of certificates which do not expire, RFC 5280 section 4.1.2.5
+const NO_EXPIRATION: &str = "99991231235959Z";
+
+#[cfg(feature = "sign")]
+/// Subject alternative name, encoded as a `GeneralName`
+enum SubjectAltName {
+    Email(String),
+    DnsName(String),
+    IpAddress(IpAddr),
+}
+
+#[cfg(feature = "sign")]
+impl SubjectAltName {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Email(email) => der::encode(der::context(1, false), email.as_bytes()),
+            Self::DnsName(name) => der::encode(der::context(2, false), name.as_bytes()),
+            Self::IpAddress(IpAddr::V4(ip)) => der::encode(der::context(7, false), &ip.octets()),
+            Self::IpAddress(IpAddr::V6(ip)) => der::encode(der::context(7, false), &ip.octets()),
+        }
+    }
+}
+
+#[cfg(feature = "sign")]
+/// Subject of a certificate, and its alternative names
+pub struct CertificateParams {
+    common_name: String,
+    subject_alt_names: Vec<SubjectAltName>,
+}
+
+#[derive(Debug, Snafu)]
+/// Errors when extracting a key from a certificate
+pub enum X509Error {
+    #[snafu(display("armor of PEM"))]
+    /// Missing `BEGIN` or `END` line, or invalid base64
+    Pem {
+        /// Original error
+        source: crate::armor::ArmorError,
+    },
+    #[snafu(display("malformed DER structure"))]
+    /// Structure is truncated, or is not an Ed25519 certificate
+    Der,
+    #[snafu(display("verify self-signature of certificate"))]
+    /// The certificate is not signed by its own key
+    Verify {
+        /// Original error
+        source: libsignify::Error,
+    },
+}
+
+#[cfg(feature = "sign")]
+impl CertificateParams {
+    /// Certificate of the subject with this common name, without alternative names
+    pub fn new(common_name: impl Into<String>) -> Self {
+        Self {
+            common_name: common_name.into(),
+            subject_alt_names: Vec::new(),
+        }
+    }
+
+    /// Add a DNS name to the alternative names
+    pub fn dns_name(mut self, name: impl Into<String>) -> Self {
+        self.subject_alt_names
+            .push(SubjectAltName::DnsName(name.into()));
+        self
+    }
+
+    /// Add an IP address to the alternative names
+    pub fn ip_address(mut self, ip: IpAddr) -> Self {
+        self.subject_alt_names.push(SubjectAltName::IpAddress(ip));
+        self
+    }
+
+    /// Add an email address to the alternative names
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.subject_alt_names
+            .push(SubjectAltName::Email(email.into()));
+        self
+    }
+
+    /// `Name` made of the common name alone
+    fn name(&self) -> Vec<u8> {
+        der::sequence(&[&der::encode(
+            der::SET,
+            &der::sequence(&[
+                &der::encode(der::OID, COMMON_NAME_OID),
+                &der::encode(UTF8_STRING, self.common_name.as_bytes()),
+            ]),
+        )])
+    }
+}
+
+#[cfg(feature = "sign")]
+/// Encode an extension, its value wrapped in an octet string
+fn extension(oid: &[u8], critical: bool, value: &[u8]) -> Vec<u8> {
+    let critical = if critical {
+        der::encode(BOOLEAN, &[0xff])
+    } else {
+        Vec::new()
+    };
+    der::sequence(&[
+        &der::encode(der::OID, oid),
+        &critical,
+        &der::encode(der::OCTET_STRING, value),
+    ])
+}
+
+#[cfg(feature = "sign")]
+/// Encode a time, in `UTCTime` until 2049 and `GeneralizedTime` after, RFC 5280 section 4.1.2.5
+fn encode_time(timestamp: Option<Timestamp>) -> Vec<u8> {
+    match timestamp {
+        Some(timestamp) if (1950..2050).contains(&timestamp.to_zoned(TimeZone::UTC).year()) => {
+            der::encode(
+                UTC_TIME,
+                timestamp.strftime("%y%m%d%H%M%SZ").to_string().as_bytes(),
+            )
+        }
+        Some(timestamp) => der::encode(
+            GENERALIZED_TIME,
+            timestamp.strftime("%Y%m%d%H%M%SZ").to_string().as_bytes(),
+        ),
+        None => der::encode(GENERALIZED_TIME, NO_EXPIRATION.as_bytes()),
+    }
+}
+
+/// Decode a `UTCTime` or `GeneralizedTime`, `None` when the certificate does not expire
+fn read_time(reader: &mut Reader) -> Result<Option<Timestamp>, X509Error> {
+    let (century, time) = match reader.peek() {
+        Some(UTC_TIME) => {
+            let time = reader.read(UTC_TIME).ok_or(X509Error::Der)?;
+            let century = if time.first().is_some_and(|digit| *digit < b'5') {
+                "20"
+            } else {
+                "19"
+            };
+            (century, time)
+        }
+        Some(GENERALIZED_TIME) => ("", reader.read(GENERALIZED_TIME).ok_or(X509Error::Der)?),
+        _ => return Err(X509Error::Der),
+    };
+    if time == NO_EXPIRATION.as_bytes() {
+        return Ok(None);
+    }
+
+    let time = std::str::from_utf8(time).map_err(|_| X509Error::Der)?;
+    jiff::fmt::strtime::parse("%Y%m%d%H%M%SZ", format!("{century}{time}"))
+        .and_then(|time| time.to_datetime())
+        .and_then(|time| time.to_zoned(TimeZone::UTC))
+        .map(|time| Some(time.timestamp()))
+        .map_err(|_| X509Error::Der)
+}
+
+#[cfg(feature = "sign")]
+impl<C> SigningKey<C> {
+    /// Export the public key as a self-signed certificate in DER
+    pub fn to_x509_der(&self, params: &CertificateParams) -> Vec<u8> {
+        let public_key = PublicKey::<()>::from_key_bytes(
+            &self.secret_key.public().key(),
+            Some(self.keynum().as_ref().try_into().expect("8 bytes")),
+        );
+        let spki = public_key.to_spki_der();
+        let name = params.name();
+        let validity = der::sequence(&[
+            &encode_time(Some(self.metadata.created_at)),
+            &encode_time(self.metadata.expired_at),
+        ]);
+
+        // Positive serial number, unique for a key, a subject and a validity. The top byte is never
+        // zero, which would make the DER integer non-minimal.
+        let mut serial = Sha256::new();
+        serial.update(&spki);
+        serial.update(&name);
+        serial.update(&validity);
+        let mut serial = serial.finalize()[..16].to_vec();
+        serial[0] = (serial[0] & 0x7f) | 0x40;
+
+        // Key identifier of RFC 7093, section 2, method 1
+        let key_id = &Sha256::digest(public_key.key_bytes())[..20];
+
+        let mut extensions = vec![
+            extension(BASIC_CONSTRAINTS_OID, true, &der::sequence(&[])),
+            // digitalSignature only
+            extension(
+                KEY_USAGE_OID,
+                true,
+                &der::encode(der::BIT_STRING, &[7, 0x80]),
+            ),
+            extension(
+                SUBJECT_KEY_IDENTIFIER_OID,
+                false,
+                &der::encode(der::OCTET_STRING, key_id),
+            ),
+            extension(
+                crate::pkcs8::ATTRIBUTE_OID,
+                false,
+                &der::encode(der::OCTET_STRING, public_key.keynum().as_ref()),
+            ),
+        ];
+        if !params.subject_alt_names.is_empty() {
+            let names: Vec<Vec<u8>> = params
+                .subject_alt_names
+                .iter()
+                .map(SubjectAltName::encode)
+                .collect();
+            extensions.push(extension(
+                SUBJECT_ALT_NAME_OID,
+                false,
+                &der::encode(der::SEQUENCE, &names.concat()),
+            ));
+        }
+
+        let tbs = der::sequence(&[
+            &der::encode(der::context(0, true), &der::encode(der::INTEGER, &[2])),
+            &der::encode(der::INTEGER, &serial),
+            &der::ed25519_algorithm(),
+            &name,
+            &validity,
+            &name,
+            &spki,
+            &der::encode(
+                der::context(3, true),
+                &der::encode(der::SEQUENCE, &extensions.concat()),
+            ),
+        ]);
+        let signature = self.sign_raw(&tbs);
+
+        der::sequence(&[
+            &tbs,
+            &der::ed25519_algorithm(),
+            &der::bit_string(&signature),
+        ])
+    }
+
+    /// Export the public key as a self-signed certificate in PEM, `-----BEGIN CERTIFICATE-----`
+    pub fn to_x509_pem(&self, params: &CertificateParams) -> String {
+        crate::armor::encode_plain(
+            CERTIFICATE_LABEL,
+            &self.to_x509_der(params),
+            crate::pkcs8::PEM_LINE_LEN,
+        )
+    }
+}
+
+impl<C> PublicKey<C> {
+    /// Extract the key of a self-signed certificate in DER, after verifying its signature
+    ///
+    /// Creation and expiration of the key are the validity of the certificate, see the
+    /// [module documentation](self) for the key number.
+    pub fn from_x509_der(der: &[u8]) -> Result<Self, X509Error> {
+        let mut outer = Reader(der);
+        let mut certificate = Reader(outer.read(der::SEQUENCE).ok_or(X509Error::Der)?);
+        let tbs = certificate.read(der::SEQUENCE).ok_or(X509Error::Der)?;
+        certificate.read_ed25519_algorithm().ok_or(X509Error::Der)?;
+        let signature = certificate.read_bit_string().ok_or(X509Error::Der)?;
+        if !outer.is_empty() || !certificate.is_empty() {
+            return Err(X509Error::Der);
+        }
+
+        let mut fields = Reader(tbs);
+        fields.read(der::context(0, true)).ok_or(X509Error::Der)?;
+        fields.read(der::INTEGER).ok_or(X509Error::Der)?;
+        fields.read_ed25519_algorithm().ok_or(X509Error::Der)?;
+        fields.read(der::SEQUENCE).ok_or(X509Error::Der)?;
+        let mut validity = Reader(fields.read(der::SEQUENCE).ok_or(X509Error::Der)?);
+        let created_at = read_time(&mut validity)?.ok_or(X509Error::Der)?;
+        let expired_at = read_time(&mut validity)?;
+        fields.read(der::SEQUENCE).ok_or(X509Error::Der)?;
+        let spki = der::encode(
+            der::SEQUENCE,
+            fields.read(der::SEQUENCE).ok_or(X509Error::Der)?,
+        );
+        let key = PublicKey::<()>::from_spki_der(&spki).map_err(|_| X509Error::Der)?;
+
+        // Unique identifiers, then extensions
+        let mut keynum = None;
+        for n in 1..=2 {
+            if fields.peek() == Some(der::context(n, false)) {
+                fields.read(der::context(n, false)).expect("peeked");
+            }
+        }
+        if fields.peek() == Some(der::context(3, true)) {
+            let extensions = Reader(fields.read(der::context(3, true)).expect("peeked"))
+                .read(der::SEQUENCE)
+                .ok_or(X509Error::Der)?;
+            let mut extensions = Reader(extensions);
+            while !extensions.is_empty() {
+                let mut extension = Reader(extensions.read(der::SEQUENCE).ok_or(X509Error::Der)?);
+                if extension.read(der::OID).ok_or(X509Error::Der)? == crate::pkcs8::ATTRIBUTE_OID {
+                    if extension.peek() == Some(BOOLEAN) {
+                        extension.read(BOOLEAN).expect("peeked");
+                    }
+                    let value = Reader(extension.read(der::OCTET_STRING).ok_or(X509Error::Der)?)
+                        .read(der::OCTET_STRING)
+                        .and_then(|keynum| <[u8; 8]>::try_from(keynum).ok())
+                        .ok_or(X509Error::Der)?;
+                    keynum = Some(value);
+                }
+            }
+        }
+        if !fields.is_empty() {
+            return Err(X509Error::Der);
+        }
+
+        let key_bytes = key.key_bytes();
+        let mut public_key = Self::from_key_bytes(&key_bytes, keynum);
+        public_key
+            .verify_raw(&der::encode(der::SEQUENCE, tbs), signature)
+            .context(VerifySnafu)?;
+        public_key.metadata.created_at = created_at;
+        public_key.metadata.expired_at = expired_at;
+
+        Ok(public_key)
+    }
+
+    /// Extract the key of a self-signed certificate in PEM, `-----BEGIN CERTIFICATE-----`
+    pub fn from_x509_pem(pem: &str) -> Result<Self, X509Error> {
+        let (_, der) = crate::armor::decode(CERTIFICATE_LABEL, pem).context(PemSnafu)?;
+        Self::from_x509_der(&der)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::SignatureBuilder;
+    use crate::test_keys::other_signing_key_with;
+
+    /// Key expiring in 2060, for a validity ending after 2050
+    fn expiring_key() -> SigningKey<String> {
+        other_signing_key_with(Some("2060-01-01T00:00:00Z"), Some("testing key".into()))
+    }
+
+    fn params() -> CertificateParams {
+        CertificateParams::new("toto")
+            .dns_name("localhost")
+            .ip_address(IpAddr::from([127, 0, 0, 1]))
+            .email("toto@example.com")
+    }
+
+    #[test]
+    fn roundtrip() {
+        let pem = expiring_key().to_x509_pem(&params());
+        let public_key = PublicKey::<String>::from_x509_pem(&pem).unwrap();
+
+        assert_eq!(public_key.keynum(), expiring_key().keynum());
+        assert_eq!(public_key.created_at(), expiring_key().created_at());
+        assert_eq!(public_key.expired_at(), expiring_key().expired_at());
+        assert!(public_key.comment().is_none());
+
+        // The extracted key verifies signatures of the signing key
+        let builder = SignatureBuilder::<_, ()>::new(String::from("toto"));
+        let signature = expiring_key().sign(builder).unwrap();
+        assert_eq!(signature.verify(&public_key).unwrap().data(), "toto");
+    }
+
+    #[test]
+    fn validity() {
+        let der = expiring_key().to_x509_der(&params());
+        let key = PublicKey::<()>::from_x509_der(&der).unwrap();
+        // UTCTime before 2050, GeneralizedTime after
+        assert!(der.windows(15).any(|time| time == b"\x17\x0d241222232147Z"));
+        assert!(
+            der.windows(17)
+                .any(|time| time == b"\x18\x0f20600101000000Z")
+        );
+        assert_eq!(key.expired_at(), Some(2840140800));
+
+        let mut signing_key = expiring_key();
+        signing_key.metadata.expired_at = None;
+        let der = signing_key.to_x509_der(&params());
+        assert!(der.windows(15).any(|time| time == NO_EXPIRATION.as_bytes()));
+        let key = PublicKey::<()>::from_x509_der(&der).unwrap();
+        assert_eq!(key.expired_at(), None);
+    }
+
+    #[test]
+    fn subject_alt_names() {
+        let der = expiring_key().to_x509_der(&params());
+        let names = [
+            &der::encode(der::context(2, false), b"localhost")[..],
+            &der::encode(der::context(7, false), &[127, 0, 0, 1]),
+            &der::encode(der::context(1, false), b"toto@example.com"),
+        ]
+        .concat();
+        assert!(der.windows(names.len()).any(|window| window == names));
+
+        // Without alternative names, the extension is left out
+        let der = expiring_key().to_x509_der(&CertificateParams::new("toto"));
+        assert!(
+            !der.windows(5)
+                .any(|window| window == [0x06, 0x03, 0x55, 0x1d, 0x11])
+        );
+    }
+
+    #[test]
+    fn altered() {
+        let mut der = expiring_key().to_x509_der(&params());
+        let position = der.windows(4).position(|name| name == b"toto").unwrap();
+        der[position] = b'T';
+
+        assert!(matches!(
+            PublicKey::<()>::from_x509_der(&der).unwrap_err(),
+            X509Error::Verify { .. }
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            PublicKey::<()>::from_x509_der(&[0x30, 0x00]).unwrap_err(),
+            X509Error::Der
+        ));
+        assert!(matches!(
+            PublicKey::<()>::from_x509_pem("toto").unwrap_err(),
+            X509Error::Pem { .. }
+        ));
+    }
+}